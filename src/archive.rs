@@ -0,0 +1,145 @@
+use std::{
+    fs::{create_dir_all, File},
+    io,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use colored::Colorize;
+use tempfile::TempDir;
+use zip::ZipArchive;
+
+use crate::{error::SyncError, has_extension, interrupt};
+
+/// Whether the path names a zip archive
+pub fn is_archive(path: &Path) -> bool {
+    has_extension(path, &["zip".to_string()])
+}
+
+/// Temporary directory archives are extracted into, removed with everything in it when dropped
+/// or when the run is interrupted
+#[derive(Debug)]
+pub struct Staging {
+    root: Mutex<Option<Root>>,
+    /// Number of archives extracted so far, each into its own folder
+    extracted: Mutex<usize>,
+}
+
+/// The directory of a [`Staging`], created only once an archive is extracted
+#[derive(Debug)]
+struct Root {
+    dir: TempDir,
+    _staged: interrupt::Staged,
+}
+
+impl Staging {
+    pub fn new() -> Self {
+        Self {
+            root: Mutex::new(None),
+            extracted: Mutex::new(0),
+        }
+    }
+
+    /// The staging directory, created with a random name only its owner can access so no other
+    /// user can plant files or links in it
+    fn root(&self) -> Result<PathBuf, SyncError> {
+        let mut root = self.root.lock().expect("staging lock");
+        if let Some(root) = root.as_ref() {
+            return Ok(root.dir.path().to_path_buf());
+        }
+        let dir = tempfile::Builder::new()
+            .prefix("subsync-")
+            .tempdir()
+            .map_err(SyncError::io(&std::env::temp_dir()))?;
+        let path = dir.path().to_path_buf();
+        *root = Some(Root {
+            _staged: interrupt::staged(&path),
+            dir,
+        });
+        Ok(path)
+    }
+
+    /// Extracts the entries of `archive` with one of `extensions`, keeping the folders they're
+    /// in, and returns the folder named after the archive's stem holding them
+    pub fn extract(&self, archive: &Path, extensions: &[String]) -> Result<PathBuf, SyncError> {
+        let invalid = |source| SyncError::Archive {
+            path: archive.to_path_buf(),
+            source,
+        };
+        let file = File::open(archive).map_err(SyncError::io(archive))?;
+        let mut zip = ZipArchive::new(file).map_err(invalid)?;
+
+        let root = self.root()?;
+        let dir = {
+            let mut extracted = self.extracted.lock().expect("staging lock");
+            *extracted += 1;
+            root.join(extracted.to_string())
+                .join(archive.file_stem().unwrap_or_default())
+        };
+        create_dir_all(&dir).map_err(SyncError::io(&dir))?;
+
+        for i in 0..zip.len() {
+            let mut entry = zip.by_index(i).map_err(invalid)?;
+            if entry.is_dir() {
+                continue;
+            }
+            let name = entry.name().map_err(invalid)?.to_string();
+            let Some(relative) = entry.enclosed_name() else {
+                warn!(
+                    "{}",
+                    format!(
+                        "Skipping {} in {}: unsafe path",
+                        name,
+                        archive.to_string_lossy()
+                    )
+                    .yellow()
+                );
+                continue;
+            };
+            if !has_extension(&relative, extensions) {
+                debug!("Skipping {name}: not a subtitle");
+                continue;
+            }
+            let target = dir.join(relative);
+            if let Some(parent) = target.parent() {
+                create_dir_all(parent).map_err(SyncError::io(parent))?;
+            }
+            let mut out = File::create(&target).map_err(SyncError::io(&target))?;
+            io::copy(&mut entry, &mut out).map_err(SyncError::io(&target))?;
+        }
+        verbose!(
+            "Extracted {} to {}",
+            archive.to_string_lossy(),
+            dir.to_string_lossy()
+        );
+        Ok(dir)
+    }
+
+    /// Whether the path was extracted from an archive, so it's removed once the run ends
+    pub fn contains(&self, path: &Path) -> bool {
+        self.root
+            .lock()
+            .expect("staging lock")
+            .as_ref()
+            .is_some_and(|root| path.starts_with(root.dir.path()))
+    }
+}
+
+impl Drop for Staging {
+    fn drop(&mut self) {
+        let Some(root) = self.root.get_mut().expect("staging lock").take() else {
+            return;
+        };
+        let path = root.dir.path().to_path_buf();
+        if let Err(e) = root.dir.close() {
+            warn!(
+                "{}",
+                format!(
+                    "Couldn't remove extracted subtitles in {}: {e}",
+                    path.to_string_lossy()
+                )
+                .yellow()
+            );
+        }
+    }
+}
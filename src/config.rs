@@ -0,0 +1,221 @@
+use std::{
+    env,
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    error::SyncError, template::Template, Eol, Fallback, MatchBy, Metric, Normalize, Severity,
+    SubtitleSelectionStrategy, SyncArgs,
+};
+
+/// Name of the config file looked up in the current directory and `$XDG_CONFIG_HOME/subsync/`
+pub const FILE_NAME: &str = "subsync.toml";
+
+/// Defaults for sync flags read from `subsync.toml`. Each key is named after the long flag it
+/// sets, such as `min-size = "10k"` for `--min-size 10k`. Flags given on the command line take
+/// precedence
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Config {
+    copy: bool,
+    hardlink: bool,
+    no_fallback: bool,
+    overwrite: bool,
+    skip_existing: bool,
+    sidecar: bool,
+    ext: Option<Vec<String>>,
+    out_ext: Option<String>,
+    convert: bool,
+    media_ext: Option<Vec<String>>,
+    fuzzy: Option<f64>,
+    similarity: Option<f64>,
+    metric: Option<Metric>,
+    contains: bool,
+    match_by: Option<MatchBy>,
+    merge: bool,
+    recursive: bool,
+    follow_symlinks: bool,
+    include_hidden: bool,
+    exclude_specials: bool,
+    relative: bool,
+    strategy: Option<SubtitleSelectionStrategy>,
+    fallback: Option<Fallback>,
+    reverse: bool,
+    natural_sort: bool,
+    learn: bool,
+    confirm: bool,
+    review: bool,
+    keyword: Option<String>,
+    non_interactive: bool,
+    prompt_timeout: Option<u64>,
+    lang: Option<String>,
+    template: Option<String>,
+    all: bool,
+    lang_dirs: bool,
+    transcode: bool,
+    normalize_text: bool,
+    eol: Option<Eol>,
+    verify: bool,
+    dedup: bool,
+    preserve_mtime: bool,
+    check_embedded: bool,
+    skip_embedded: bool,
+    mkdir: bool,
+    yes: bool,
+    prune: bool,
+    backup: bool,
+    jobs: Option<u16>,
+    min_size: Option<String>,
+    max_size: Option<String>,
+    include_symlinks: bool,
+    sub_depth: Option<usize>,
+    ignore_case: bool,
+    normalize: Option<Normalize>,
+    validate: Option<Severity>,
+}
+
+/// Locations searched for a config file, in order of preference
+fn search_paths() -> Vec<PathBuf> {
+    let mut paths = vec![PathBuf::from(FILE_NAME)];
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|v| !v.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")));
+    if let Some(config_home) = config_home {
+        paths.push(config_home.join("subsync").join(FILE_NAME));
+    }
+    paths
+}
+
+impl Config {
+    /// Reads the first config file found, or an empty config if there is none
+    pub fn load() -> Result<Self, SyncError> {
+        let Some(path) = search_paths().into_iter().find(|p| p.is_file()) else {
+            return Ok(Self::default());
+        };
+        verbose!("Using config {}", path.to_string_lossy());
+
+        let content = read_to_string(&path).map_err(SyncError::io(&path))?;
+        let config: Self = toml::from_str(&content).map_err(|e| SyncError::Config {
+            path: path.clone(),
+            message: e.message().to_string(),
+        })?;
+
+        let invalid = |message: String| SyncError::Config {
+            path: path.clone(),
+            message,
+        };
+        for threshold in [config.fuzzy, config.similarity].into_iter().flatten() {
+            crate::parse_threshold(&threshold.to_string()).map_err(invalid)?;
+        }
+        if let Some(template) = &config.template {
+            Template::parse(template).map_err(invalid)?;
+        }
+        for size in [&config.min_size, &config.max_size].into_iter().flatten() {
+            crate::parse_size(size).map_err(invalid)?;
+        }
+        if config.jobs == Some(0) {
+            return Err(invalid("jobs must be at least 1".to_string()));
+        }
+        if config.prompt_timeout == Some(0) {
+            return Err(invalid("prompt-timeout must be at least 1".to_string()));
+        }
+        Ok(config)
+    }
+
+    /// Fills in every flag not given on the command line. Booleans can only be turned on, and
+    /// defaults that would conflict with a given flag are ignored
+    pub fn apply(self, cli: &mut SyncArgs) {
+        // Media linked with --reverse-link is never copied, renamed, or given a sidecar
+        let subtitles_placed = !cli.reverse_link;
+        if !cli.copy && !cli.hardlink {
+            cli.copy = self.copy && subtitles_placed;
+            cli.hardlink = self.hardlink && !self.copy;
+        }
+        cli.no_fallback |= self.no_fallback && !cli.copy && !cli.hardlink;
+        cli.overwrite |= self.overwrite;
+        cli.skip_existing |= self.skip_existing;
+        cli.sidecar |= self.sidecar && subtitles_placed;
+        cli.extensions = cli.extensions.take().or(self.ext);
+        if subtitles_placed {
+            cli.out_ext = cli.out_ext.take().or(self.out_ext);
+        }
+        cli.convert |= self.convert && subtitles_placed;
+        cli.media_ext = cli.media_ext.take().or(self.media_ext);
+        // --fuzzy and --similarity conflict, so one given on the command line keeps the other
+        // from the config
+        if cli.similarity.is_none() {
+            cli.fuzzy = cli.fuzzy.or(self.fuzzy);
+        }
+        if cli.fuzzy.is_none() {
+            cli.similarity = cli.similarity.or(self.similarity);
+            cli.metric = cli.metric.or(self.metric);
+        }
+        cli.contains |= self.contains;
+        cli.match_by = cli.match_by.or(self.match_by);
+        cli.recursive |= self.recursive;
+        cli.follow_symlinks |= self.follow_symlinks;
+        cli.include_hidden |= self.include_hidden;
+        cli.exclude_specials |= self.exclude_specials && !cli.include_specials;
+        cli.relative |= self.relative;
+        cli.all |= self.all && cli.strategy.is_none() && !cli.lang_dirs && subtitles_placed;
+        if !cli.all {
+            cli.strategy = cli.strategy.or(self.strategy);
+        }
+        cli.merge |= self.merge && !cli.all && !cli.lang_dirs && subtitles_placed;
+        cli.lang_dirs |=
+            self.lang_dirs && !cli.all && !cli.merge && cli.lang.is_none() && subtitles_placed;
+        cli.fallback = cli.fallback.or(self.fallback);
+        cli.reverse |= self.reverse;
+        cli.natural_sort |= self.natural_sort;
+        cli.learn |= self.learn;
+        cli.keyword = cli.keyword.take().or(self.keyword);
+        cli.non_interactive |= self.non_interactive
+            && !cli.confirm
+            && (cli.keyword.is_some() || cli.keyword_regex.is_some());
+        cli.confirm |= self.confirm && !cli.non_interactive;
+        cli.review |= self.review && !cli.non_interactive;
+        cli.prompt_timeout = cli.prompt_timeout.or(self.prompt_timeout);
+        if subtitles_placed && !cli.lang_dirs {
+            cli.lang = cli.lang.take().or(self.lang);
+        }
+        if cli.template.is_none() && subtitles_placed {
+            cli.template = self
+                .template
+                .map(|t| Template::parse(&t).expect("template validated on load"));
+        }
+        cli.transcode |= self.transcode && cli.copy;
+        cli.normalize_text |= self.normalize_text && cli.copy && !cli.verify;
+        if cli.normalize_text {
+            cli.eol = cli.eol.or(self.eol);
+        }
+        cli.verify |= self.verify && cli.copy && !cli.transcode && !cli.normalize_text;
+        cli.dedup |= self.dedup && cli.copy;
+        cli.preserve_mtime |= self.preserve_mtime;
+        cli.mkdir |= self.mkdir;
+        cli.check_embedded |= self.check_embedded;
+        cli.skip_embedded |= self.skip_embedded;
+        cli.yes |= self.yes;
+        cli.prune |= self.prune;
+        cli.backup |= self.backup && cli.overwrite;
+        cli.jobs = cli.jobs.or(self.jobs);
+        if cli.min_size.is_none() {
+            cli.min_size = self
+                .min_size
+                .map(|s| crate::parse_size(&s).expect("size validated on load"));
+        }
+        if cli.max_size.is_none() {
+            cli.max_size = self
+                .max_size
+                .map(|s| crate::parse_size(&s).expect("size validated on load"));
+        }
+        cli.include_symlinks |= self.include_symlinks;
+        cli.sub_depth = cli.sub_depth.or(self.sub_depth);
+        cli.ignore_case |= self.ignore_case;
+        cli.normalize = cli.normalize.or(self.normalize);
+        cli.validate = cli.validate.or(self.validate);
+    }
+}
@@ -0,0 +1,196 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{canonicalize, metadata, read_dir, read_to_string, symlink_metadata, DirEntry},
+    path::{Path, PathBuf},
+};
+
+use crate::{error::SyncError, has_extension, is_media, sidecar};
+
+/// How directories are walked when looking for files
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Walk {
+    /// Descend into nested directories
+    pub recursive: bool,
+    /// Resolve symlinks, so links to directories count as directories. Otherwise every symlink
+    /// counts as a file named after the link, whatever it points at
+    pub follow_symlinks: bool,
+    /// List files and directories whose name starts with a dot, which are skipped otherwise
+    pub include_hidden: bool,
+}
+
+impl Walk {
+    /// Whether `path` is listed at all, see [`Walk::include_hidden`]
+    pub fn lists(&self, path: &Path) -> bool {
+        self.include_hidden || !is_hidden(path)
+    }
+}
+
+/// Whether a directory entry is a directory, resolving symlinks only when `follow_symlinks` is
+/// set
+pub fn is_dir(entry: &DirEntry, follow_symlinks: bool) -> bool {
+    if follow_symlinks {
+        entry.path().is_dir()
+    } else {
+        entry.file_type().is_ok_and(|t| t.is_dir())
+    }
+}
+
+/// Name of a media file without its last extension, such as `Show.S01E01.1080p` for
+/// `Show.S01E01.1080p.mkv`. Names without an extension are kept whole
+pub fn stem(path: &Path) -> Option<String> {
+    Some(path.file_stem()?.to_string_lossy().to_string())
+}
+
+/// Whether the file name starts with a dot, such as the `._Show.S01E01.mkv` metadata files macOS
+/// leaves on other filesystems
+pub fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
+
+/// Lists the files in `dir`, sorted within each directory, skipping hidden ones unless
+/// `walk.include_hidden` is set.
+/// When `walk.recursive` is set, nested directories are walked as well, each only once even if
+/// followed symlinks lead back to it
+pub fn files(dir: &Path, walk: Walk) -> Result<Vec<PathBuf>, SyncError> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    let mut visited = HashSet::new();
+
+    while let Some(dir) = pending.pop() {
+        if walk.follow_symlinks && !visited.insert(canonicalize(&dir).unwrap_or(dir.clone())) {
+            continue;
+        }
+        let mut entries: Vec<_> = read_dir(&dir)
+            .map_err(SyncError::io(&dir))?
+            .flatten()
+            .filter(|de| walk.lists(&de.path()))
+            .map(|de| (is_dir(&de, walk.follow_symlinks), de.path()))
+            .collect();
+        entries.sort_unstable_by(|a, b| a.1.cmp(&b.1));
+
+        for (is_dir, path) in entries {
+            if is_dir {
+                if walk.recursive {
+                    pending.push(path);
+                }
+                continue;
+            }
+            files.push(path);
+        }
+    }
+
+    Ok(files)
+}
+
+/// Collects the files in `dir` with one of `media_extensions` grouped by their stem, walking
+/// it as `walk` says
+pub fn scan(
+    dir: &Path,
+    media_extensions: &[String],
+    walk: Walk,
+) -> Result<HashMap<String, Vec<PathBuf>>, SyncError> {
+    let mut stems: HashMap<String, Vec<PathBuf>> = HashMap::new();
+
+    for path in files(dir, walk)? {
+        if !is_media(&path, media_extensions) {
+            continue;
+        }
+
+        let Some(stem) = stem(&path) else {
+            continue;
+        };
+        debug!("Found media {}", path.to_string_lossy());
+        stems.entry(stem).or_default().push(path);
+    }
+
+    Ok(stems)
+}
+
+/// Reads the media files listed with --media-list instead of scanning a directory, grouped by
+/// their stem like [`scan`]. The list is either a JSON array of paths or one path per line, and
+/// relative paths are resolved against `base`. Listed files without one of `media_extensions`
+/// are skipped, so a full listing of a remote directory can be used as is
+pub fn listed(
+    list: &Path,
+    base: &Path,
+    media_extensions: &[String],
+) -> Result<HashMap<String, Vec<PathBuf>>, SyncError> {
+    let content = read_to_string(list).map_err(SyncError::io(list))?;
+    let paths: Vec<PathBuf> = if content.trim_start().starts_with('[') {
+        serde_json::from_str(&content).map_err(|e| SyncError::MediaList {
+            path: list.to_path_buf(),
+            message: e.to_string(),
+        })?
+    } else {
+        content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .map(PathBuf::from)
+            .collect()
+    };
+
+    let mut stems: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    for path in paths {
+        if !is_media(&path, media_extensions) {
+            continue;
+        }
+        let Some(stem) = stem(&path) else {
+            continue;
+        };
+        let path = base.join(path);
+        debug!("Listed media {}", path.to_string_lossy());
+        let files = stems.entry(stem).or_default();
+        if !files.contains(&path) {
+            files.push(path);
+        }
+    }
+
+    Ok(stems)
+}
+
+/// Finds the subtitles in `dir` that no longer belong to a media file, either because no media
+/// in their folder has a stem their name starts with or because they're dangling symlinks,
+/// along with the sidecars of those subtitles and of subtitles that are gone
+pub fn orphans(
+    dir: &Path,
+    media_extensions: &[String],
+    subtitle_extensions: &[String],
+    walk: Walk,
+) -> Result<Vec<PathBuf>, SyncError> {
+    let files = files(dir, walk)?;
+    let media: Vec<_> = files
+        .iter()
+        .filter(|path| is_media(path, media_extensions))
+        .filter_map(|path| {
+            let stem = path.file_stem()?.to_string_lossy();
+            Some((path.parent(), format!("{stem}.")))
+        })
+        .collect();
+
+    let mut orphans: Vec<_> = files
+        .iter()
+        .filter(|path| has_extension(path, subtitle_extensions))
+        .filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let dangling = metadata(path).is_err();
+            dangling
+                || !media
+                    .iter()
+                    .any(|(parent, prefix)| *parent == path.parent() && name.starts_with(prefix))
+        })
+        .cloned()
+        .collect();
+    let sidecars: Vec<_> = files
+        .iter()
+        .filter(|path| {
+            sidecar::target(path).is_some_and(|target| {
+                symlink_metadata(&target).is_err() || orphans.contains(&target)
+            })
+        })
+        .cloned()
+        .collect();
+    orphans.extend(sidecars);
+    Ok(orphans)
+}
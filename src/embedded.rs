@@ -0,0 +1,93 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::Mutex,
+};
+
+use colored::Colorize;
+
+/// Subtitle streams already embedded in media files, looked up with `ffprobe` for
+/// --check-embedded. Each media file is probed once
+#[derive(Debug)]
+pub struct Embedded {
+    /// Leave media with embedded subtitles alone instead of only warning about it
+    pub skip: bool,
+    streams: Mutex<HashMap<PathBuf, usize>>,
+}
+
+/// Counts the subtitle streams of `media`
+fn probe(media: &Path) -> Result<usize, String> {
+    let output = Command::new("ffprobe")
+        .args(["-v", "error", "-select_streams", "s"])
+        .args(["-show_entries", "stream=index", "-of", "csv=p=0"])
+        .arg(media)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(stderr.lines().next().unwrap_or_default().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .count())
+}
+
+impl Embedded {
+    /// Checks that `ffprobe` can be run, warning and returning `None` when it can't so the run
+    /// goes on without checking
+    pub fn new(skip: bool) -> Option<Self> {
+        let found = Command::new("ffprobe")
+            .arg("-version")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok();
+        if !found {
+            warn!(
+                "{}",
+                "ffprobe wasn't found, embedded subtitles won't be checked".yellow()
+            );
+            return None;
+        }
+        Some(Self {
+            skip,
+            streams: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Whether `media` already has subtitle streams, warning the first time it's probed. Media
+    /// ffprobe can't read is assumed to have none
+    pub fn found(&self, media: &Path) -> bool {
+        if let Some(count) = self.streams.lock().expect("streams lock").get(media) {
+            return *count > 0;
+        }
+        let count = match probe(media) {
+            Ok(count) => count,
+            Err(e) => {
+                verbose!("Couldn't probe {}: {e}", media.to_string_lossy());
+                0
+            }
+        };
+        if count > 0 {
+            warn!(
+                "{}",
+                format!(
+                    "{} already has {count} embedded subtitle {}{}",
+                    media.to_string_lossy(),
+                    if count == 1 { "stream" } else { "streams" },
+                    if self.skip { ", skipping it" } else { "" }
+                )
+                .yellow()
+            );
+        }
+        self.streams
+            .lock()
+            .expect("streams lock")
+            .insert(media.to_path_buf(), count);
+        count > 0
+    }
+}
@@ -0,0 +1,63 @@
+use std::borrow::Cow;
+
+use chardetng::EncodingDetector;
+use encoding_rs::{Encoding, UTF_8};
+
+/// Subtitle text decoded to UTF-8
+pub enum Decoded<'a> {
+    /// The bytes were already valid UTF-8
+    Utf8(&'a str),
+    /// The bytes were transcoded from the detected encoding
+    Transcoded(String, &'static Encoding),
+}
+
+impl Decoded<'_> {
+    pub fn text(&self) -> &str {
+        match self {
+            Self::Utf8(text) => text,
+            Self::Transcoded(text, _) => text,
+        }
+    }
+}
+
+/// Decodes subtitle bytes to UTF-8, taking their encoding from a byte order mark, such as the
+/// one UTF-16 files from Windows tools start with, and detecting it when there's none and they
+/// aren't already UTF-8. The byte order mark is removed. Returns `None` when the encoding can't
+/// decode the bytes cleanly
+pub fn to_utf8(bytes: &[u8]) -> Option<Decoded<'_>> {
+    if let Some((encoding, _)) = Encoding::for_bom(bytes) {
+        let (text, had_errors) = encoding.decode_with_bom_removal(bytes);
+        return match text {
+            _ if had_errors => None,
+            Cow::Borrowed(text) if encoding == UTF_8 => Some(Decoded::Utf8(text)),
+            text => Some(Decoded::Transcoded(text.into_owned(), encoding)),
+        };
+    }
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return Some(Decoded::Utf8(text));
+    }
+
+    let mut detector = EncodingDetector::new();
+    detector.feed(bytes, true);
+    let encoding = detector.guess(None, true);
+    let (text, had_errors) = encoding.decode_without_bom_handling(bytes);
+    if had_errors {
+        return None;
+    }
+
+    Some(Decoded::Transcoded(
+        match text {
+            Cow::Borrowed(t) => t.to_string(),
+            Cow::Owned(t) => t,
+        },
+        encoding,
+    ))
+}
+
+/// Strips a leading byte order mark and converts every line ending to `eol`
+pub fn normalize(text: &str, eol: &str) -> String {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    text.replace("\r\n", "\n")
+        .replace('\r', "\n")
+        .replace('\n', eol)
+}
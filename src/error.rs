@@ -0,0 +1,252 @@
+use std::{
+    error::Error,
+    fmt::Display,
+    io,
+    path::{Path, PathBuf},
+};
+
+use inquire::InquireError;
+
+use zip::result::ZipError;
+
+use crate::{
+    srt::{SrtError, SrtIssue},
+    vtt::VttError,
+};
+
+#[derive(Debug)]
+pub enum SyncError {
+    /// A filesystem operation on the given path failed
+    Io {
+        path: PathBuf,
+        source: io::Error,
+    },
+    /// No subtitle candidates remained in the directory after filtering
+    NoSubtitles {
+        dir: PathBuf,
+        min_size: Option<u64>,
+        max_size: Option<u64>,
+    },
+    /// A path was missing a component required to build the target, such as a file name
+    InvalidPath(PathBuf),
+    /// A hardlink was requested to a subtitle on a different filesystem than the media
+    CrossDevice(PathBuf),
+    /// A copy's checksum didn't match its source, so the copy was removed
+    Corrupted(PathBuf),
+    /// Timing shifts, merging, or conversion were requested for a subtitle that isn't SRT or
+    /// WebVTT
+    UnsupportedTiming(PathBuf),
+    /// The folder a subtitle was to be placed in doesn't exist and --mkdir wasn't given
+    MissingDir(PathBuf),
+    /// The folder a subtitle was to be placed in is read-only
+    ReadOnlyDir(PathBuf),
+    /// Media was to be linked next to a subtitle extracted from an archive, which is removed
+    /// once the run ends
+    Extracted(PathBuf),
+    Srt {
+        path: PathBuf,
+        source: SrtError,
+    },
+    Vtt {
+        path: PathBuf,
+        source: VttError,
+    },
+    /// An archive of subtitles couldn't be read
+    Archive {
+        path: PathBuf,
+        source: ZipError,
+    },
+    /// A subtitle failed validation
+    Invalid {
+        path: PathBuf,
+        issues: Vec<SrtIssue>,
+    },
+    Prompt(InquireError),
+    /// The user chose to skip the folder when selecting a subtitle
+    Skipped,
+    /// The user chose to skip the folder and every one after it
+    SkippedRest,
+    /// The user declined the planned changes, so nothing was changed
+    Declined,
+    /// The config file couldn't be parsed or held an invalid value
+    Config {
+        path: PathBuf,
+        message: String,
+    },
+    /// The `--manifest` file couldn't be parsed
+    Manifest {
+        path: PathBuf,
+        message: String,
+    },
+    /// The `--media-list` file couldn't be parsed
+    MediaList {
+        path: PathBuf,
+        message: String,
+    },
+    /// The `--rules` file couldn't be parsed
+    Rules {
+        path: PathBuf,
+        message: String,
+    },
+    /// Another run holds the lock on the output directory
+    Locked {
+        dir: PathBuf,
+        holder: Option<u32>,
+    },
+    /// --force-unlock was given, but the run holding the lock is still running on this machine
+    StillLocked {
+        dir: PathBuf,
+        holder: u32,
+    },
+    /// The `--match-command` failed or chose a subtitle that can't be used
+    MatchCommand {
+        stem: String,
+        message: String,
+    },
+}
+
+impl SyncError {
+    /// Returns a closure wrapping an IO error with the path it occurred on, for use with `map_err`
+    pub fn io(path: &Path) -> impl FnOnce(io::Error) -> Self + '_ {
+        move |source| Self::Io {
+            path: path.to_path_buf(),
+            source,
+        }
+    }
+
+    /// Whether the error came from the user aborting a prompt or declining the plan, which
+    /// should stop the whole run
+    pub fn is_prompt(&self) -> bool {
+        matches!(self, Self::Prompt(_) | Self::Declined)
+    }
+
+    /// Whether the user chose to leave the folder unmatched
+    pub fn is_skip(&self) -> bool {
+        matches!(self, Self::Skipped | Self::SkippedRest)
+    }
+}
+
+impl Display for SyncError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io { path, source } => write!(f, "{}: {source}", path.to_string_lossy()),
+            Self::NoSubtitles {
+                dir,
+                min_size,
+                max_size,
+            } => {
+                write!(f, "No subtitles in sub directory {}", dir.to_string_lossy())?;
+                let size = |size: &u64| humansize::format_size(*size, humansize::DECIMAL);
+                match (min_size, max_size) {
+                    (Some(min), Some(max)) => {
+                        write!(f, " between {} and {}", size(min), size(max))
+                    }
+                    (Some(min), None) => write!(f, " of at least {}", size(min)),
+                    (None, Some(max)) => write!(f, " of at most {}", size(max)),
+                    (None, None) => Ok(()),
+                }
+            }
+            Self::InvalidPath(path) => write!(f, "Invalid path {}", path.to_string_lossy()),
+            Self::CrossDevice(path) => write!(
+                f,
+                "Cannot hardlink {} across filesystems, use --copy instead",
+                path.to_string_lossy()
+            ),
+            Self::Corrupted(path) => write!(
+                f,
+                "Copy {} doesn't match its source and was removed",
+                path.to_string_lossy()
+            ),
+            Self::UnsupportedTiming(path) => write!(
+                f,
+                "Cannot rewrite {}, only SRT and WebVTT subtitles can be shifted, merged, or converted",
+                path.to_string_lossy()
+            ),
+            Self::MissingDir(path) => write!(
+                f,
+                "Cannot place subtitles in {}, it doesn't exist. Use --mkdir to create it",
+                path.to_string_lossy()
+            ),
+            Self::ReadOnlyDir(path) => write!(
+                f,
+                "Cannot place subtitles in {}, it is read-only",
+                path.to_string_lossy()
+            ),
+            Self::Extracted(path) => write!(
+                f,
+                "Cannot link media next to {}, it was extracted from an archive",
+                path.to_string_lossy()
+            ),
+            Self::Srt { path, source } => {
+                write!(f, "Failed to parse {}: {source}", path.to_string_lossy())
+            }
+            Self::Vtt { path, source } => {
+                write!(f, "Failed to parse {}: {source}", path.to_string_lossy())
+            }
+            Self::Archive { path, source } => {
+                write!(f, "Failed to read {}: {source}", path.to_string_lossy())
+            }
+            Self::Invalid { path, issues } => {
+                write!(f, "{} failed validation", path.to_string_lossy())?;
+                for issue in issues {
+                    write!(f, "\n   {issue}")?;
+                }
+                Ok(())
+            }
+            Self::Prompt(e) => write!(f, "Prompt failed: {e}"),
+            Self::Skipped => f.write_str("Skipped"),
+            Self::SkippedRest => f.write_str("Skipped with every remaining folder"),
+            Self::Declined => f.write_str("Declined, nothing was changed"),
+            Self::Config { path, message } => {
+                write!(f, "Invalid config {}: {message}", path.to_string_lossy())
+            }
+            Self::Manifest { path, message } => {
+                write!(f, "Invalid manifest {}: {message}", path.to_string_lossy())
+            }
+            Self::MediaList { path, message } => {
+                write!(f, "Invalid media list {}: {message}", path.to_string_lossy())
+            }
+            Self::Rules { path, message } => {
+                write!(f, "Invalid rules {}: {message}", path.to_string_lossy())
+            }
+            Self::Locked { dir, holder } => {
+                match holder {
+                    Some(pid) => write!(f, "Another run (process {pid}) is changing ")?,
+                    None => f.write_str("Another run is changing ")?,
+                }
+                write!(
+                    f,
+                    "{}. Use --wait to wait for it, or --force-unlock if it crashed",
+                    dir.to_string_lossy()
+                )
+            }
+            Self::StillLocked { dir, holder } => write!(
+                f,
+                "The run in process {holder} is still changing {}, use --wait to wait for it",
+                dir.to_string_lossy()
+            ),
+            Self::MatchCommand { stem, message } => {
+                write!(f, "Match command failed for {stem}: {message}")
+            }
+        }
+    }
+}
+
+impl Error for SyncError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            Self::Srt { source, .. } => Some(source),
+            Self::Vtt { source, .. } => Some(source),
+            Self::Archive { source, .. } => Some(source),
+            Self::Prompt(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<InquireError> for SyncError {
+    fn from(value: InquireError) -> Self {
+        Self::Prompt(value)
+    }
+}
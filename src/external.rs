@@ -0,0 +1,98 @@
+use std::{
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use serde::Serialize;
+
+use crate::error::SyncError;
+
+/// What `--match-command` is told about each media stem, written to its stdin as JSON
+#[derive(Debug, Serialize)]
+struct Request<'a> {
+    stem: &'a str,
+    media: &'a [PathBuf],
+    input: &'a Path,
+}
+
+/// Runs `command` through the shell
+fn shell(command: &str) -> Command {
+    #[cfg(windows)]
+    let mut shell = {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    };
+    #[cfg(not(windows))]
+    let mut shell = {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    shell.arg(command);
+    shell
+}
+
+/// Asks `command` which subtitle under `input` to place for the media sharing `stem`. The first
+/// non-empty line it prints is the subtitle, relative to `input` unless absolute, and printing
+/// nothing leaves the media unmatched. The subtitle must be a file inside `input`
+pub fn choose(
+    command: &str,
+    input: &Path,
+    stem: &str,
+    media_files: &[PathBuf],
+) -> Result<Option<PathBuf>, SyncError> {
+    let failed = |message: String| SyncError::MatchCommand {
+        stem: stem.to_string(),
+        message,
+    };
+
+    let request = serde_json::to_string(&Request {
+        stem,
+        media: media_files,
+        input,
+    })
+    .expect("request to serialize");
+    let mut child = shell(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .map_err(|e| failed(e.to_string()))?;
+    let written = child
+        .stdin
+        .take()
+        .expect("stdin to be piped")
+        .write_all(request.as_bytes());
+    // Commands that don't read the request close stdin early
+    match written {
+        Err(e) if e.kind() != io::ErrorKind::BrokenPipe => return Err(failed(e.to_string())),
+        _ => {}
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| failed(e.to_string()))?;
+    if !output.status.success() {
+        return Err(failed(format!("exited with {}", output.status)));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let Some(chosen) = stdout.lines().map(str::trim).find(|line| !line.is_empty()) else {
+        return Ok(None);
+    };
+    let subtitle = input.join(chosen);
+    if !subtitle.is_file() {
+        return Err(failed(format!("{chosen} isn't a file")));
+    }
+    let inside = match (subtitle.canonicalize(), input.canonicalize()) {
+        (Ok(subtitle), Ok(input)) => subtitle.starts_with(input),
+        _ => false,
+    };
+    if !inside {
+        return Err(failed(format!(
+            "{chosen} isn't inside {}",
+            input.to_string_lossy()
+        )));
+    }
+    Ok(Some(subtitle))
+}
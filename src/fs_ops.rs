@@ -0,0 +1,172 @@
+use std::{
+    ffi::OsString,
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::{Component, Path, PathBuf},
+};
+
+use sha2::{Digest, Sha256};
+
+/// Creates a symbolic link at `dst` pointing to `src`
+#[cfg(unix)]
+pub fn link_file(src: &Path, dst: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(src, dst)
+}
+
+/// Creates a symbolic link at `dst` pointing to `src`
+#[cfg(windows)]
+pub fn link_file(src: &Path, dst: &Path) -> io::Result<()> {
+    std::os::windows::fs::symlink_file(src, dst)
+}
+
+/// Whether a failed link was caused by the filesystem not supporting symlinks, such as FAT32 or
+/// some network mounts, or by the user lacking the privilege to create them, as is the default
+/// on Windows without developer mode. Folders that can't be written to don't count
+#[cfg(unix)]
+pub fn is_unsupported_link(err: &io::Error) -> bool {
+    matches!(
+        err.raw_os_error(),
+        Some(libc::EPERM | libc::ENOSYS | libc::EOPNOTSUPP)
+    )
+}
+
+/// Whether a failed link was caused by the filesystem not supporting symlinks, such as FAT32 or
+/// some network mounts, or by the user lacking the privilege to create them, as is the default
+/// on Windows without developer mode. Folders that can't be written to don't count
+#[cfg(windows)]
+pub fn is_unsupported_link(err: &io::Error) -> bool {
+    // ERROR_PRIVILEGE_NOT_HELD
+    const PRIVILEGE_NOT_HELD: i32 = 1314;
+
+    err.kind() == io::ErrorKind::Unsupported || err.raw_os_error() == Some(PRIVILEGE_NOT_HELD)
+}
+
+/// Makes a path absolute and lexically resolves `.` and `..` components without following symlinks
+fn normalize(path: &Path) -> io::Result<PathBuf> {
+    let mut normalized = PathBuf::new();
+    for component in std::path::absolute(path)?.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            c => normalized.push(c),
+        }
+    }
+    Ok(normalized)
+}
+
+/// Computes the path to `target` relative to the directory `base`.
+/// Returns `None` when no relative path exists, such as between different Windows drives
+pub fn relative_path(base: &Path, target: &Path) -> io::Result<Option<PathBuf>> {
+    let (base, target) = (normalize(base)?, normalize(target)?);
+    let mut base = base.components().peekable();
+    let mut target = target.components().peekable();
+
+    if base.peek() != target.peek() {
+        return Ok(None);
+    }
+    while base.peek().is_some() && base.peek() == target.peek() {
+        base.next();
+        target.next();
+    }
+
+    let mut relative: PathBuf = base.map(|_| Component::ParentDir).collect();
+    relative.extend(target);
+    Ok(Some(relative))
+}
+
+/// Finds a free path to back `target` up to, trying `<target>.bak` and then `<target>.bak.N`
+pub fn backup_path(target: &Path) -> PathBuf {
+    let mut name = target.as_os_str().to_owned();
+    name.push(".bak");
+    let mut backup = PathBuf::from(name.clone());
+    let mut n = 1;
+    while std::fs::symlink_metadata(&backup).is_ok() {
+        let mut numbered = name.clone();
+        numbered.push(format!(".{n}"));
+        backup = PathBuf::from(numbered);
+        n += 1;
+    }
+    backup
+}
+
+/// Path beside `target` that it's written to before being renamed into place, hidden so
+/// scanners skip it while it's incomplete
+pub fn temp_path(target: &Path) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(target.file_name().unwrap_or_default());
+    name.push(".subsync-tmp");
+    target.with_file_name(name)
+}
+
+/// Whether both paths resolve to the same location, following symlinks. A target that doesn't
+/// exist yet is resolved through its parent directory
+pub fn same_file(source: &Path, target: &Path) -> bool {
+    let Ok(source) = source.canonicalize() else {
+        return false;
+    };
+    let target = target.canonicalize().or_else(|_| {
+        let parent = match target.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        Ok::<_, io::Error>(
+            parent
+                .canonicalize()?
+                .join(target.file_name().unwrap_or_default()),
+        )
+    });
+    target.is_ok_and(|target| target == source)
+}
+
+/// Whether `target` already provides `source`: a symlink resolving to it when `symlink` is set,
+/// otherwise a file of the same size modified no earlier than it
+pub fn up_to_date(source: &Path, target: &Path, symlink: bool) -> bool {
+    let Ok(target_meta) = std::fs::symlink_metadata(target) else {
+        return false;
+    };
+    if symlink {
+        return target_meta.is_symlink()
+            && matches!(
+                (source.canonicalize(), target.canonicalize()),
+                (Ok(source), Ok(target)) if source == target
+            );
+    }
+    let Ok(source_meta) = std::fs::metadata(source) else {
+        return false;
+    };
+    target_meta.is_file()
+        && target_meta.len() == source_meta.len()
+        && matches!(
+            (source_meta.modified(), target_meta.modified()),
+            (Ok(source), Ok(target)) if target >= source
+        )
+}
+
+/// Computes the SHA-256 checksum of a file's contents
+pub fn sha256(path: &Path) -> io::Result<Vec<u8>> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut File::open(path)?, &mut hasher)?;
+    Ok(hasher.finalize().to_vec())
+}
+
+/// Computes the hash OpenSubtitles identifies media by, returned with the file's size: the size
+/// plus the first and last 64 KiB read as little-endian 64-bit words, summed with wrapping
+pub fn opensubtitles_hash(path: &Path) -> io::Result<(u64, u64)> {
+    const CHUNK: u64 = 64 * 1024;
+    let mut file = File::open(path)?;
+    let size = file.metadata()?.len();
+    let mut hash = size;
+    for offset in [0, size.saturating_sub(CHUNK)] {
+        file.seek(SeekFrom::Start(offset))?;
+        let mut chunk = Vec::new();
+        (&mut file).take(CHUNK).read_to_end(&mut chunk)?;
+        for word in chunk.chunks(8) {
+            let mut bytes = [0; 8];
+            bytes[..word.len()].copy_from_slice(word);
+            hash = hash.wrapping_add(u64::from_le_bytes(bytes));
+        }
+    }
+    Ok((hash, size))
+}
@@ -0,0 +1,79 @@
+use std::{
+    fs::{remove_dir_all, remove_file},
+    path::{Path, PathBuf},
+    process,
+    sync::Mutex,
+};
+
+use colored::Colorize;
+
+/// Exit code of runs interrupted with Ctrl-C, following the shell convention of 128 + SIGINT
+pub const EXIT_CODE: i32 = 130;
+
+/// Targets being written right now, removed if the run is interrupted before they're complete
+static WRITING: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Folders archives were extracted into, removed if the run is interrupted
+static STAGED: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+
+/// Removes the targets being written and the extracted archives when Ctrl-C is pressed, then
+/// exits. Targets already written are kept
+pub fn install() -> Result<(), ctrlc::Error> {
+    ctrlc::set_handler(|| {
+        // Held until exiting so no other write starts in the meantime
+        let writing = WRITING.lock().unwrap_or_else(|e| e.into_inner());
+        for target in writing.iter() {
+            let _ = remove_file(target);
+        }
+        for dir in STAGED.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+            let _ = remove_dir_all(dir);
+        }
+        let message = if writing.is_empty() {
+            "Interrupted"
+        } else {
+            "Interrupted, rolled back current operation"
+        };
+        eprintln!("\n{}", message.red().bold());
+        process::exit(EXIT_CODE);
+    })
+}
+
+/// Marks `target` as being written until the returned guard is dropped
+pub fn writing(target: &Path) -> Writing {
+    WRITING
+        .lock()
+        .expect("writing lock")
+        .push(target.to_path_buf());
+    Writing(target.to_path_buf())
+}
+
+/// A target being written, see [`writing`]
+pub struct Writing(PathBuf);
+
+impl Drop for Writing {
+    fn drop(&mut self) {
+        let mut writing = WRITING.lock().expect("writing lock");
+        if let Some(i) = writing.iter().position(|target| *target == self.0) {
+            writing.swap_remove(i);
+        }
+    }
+}
+
+/// Marks `dir` as holding extracted archives until the returned guard is dropped
+pub fn staged(dir: &Path) -> Staged {
+    STAGED.lock().expect("staged lock").push(dir.to_path_buf());
+    Staged(dir.to_path_buf())
+}
+
+/// A folder of extracted archives, see [`staged`]
+#[derive(Debug)]
+pub struct Staged(PathBuf);
+
+impl Drop for Staged {
+    fn drop(&mut self) {
+        let mut staged = STAGED.lock().expect("staged lock");
+        if let Some(i) = staged.iter().position(|dir| *dir == self.0) {
+            staged.swap_remove(i);
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use std::{
+    fs::{remove_file, rename, symlink_metadata, File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+
+use crate::error::SyncError;
+
+/// Name of the journal kept in the output directory, one JSON entry per line
+pub const FILE_NAME: &str = ".subsync-journal.jsonl";
+
+/// A filesystem change made during a run
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "lowercase")]
+pub enum Entry {
+    /// A subtitle was linked, copied, or written to `target`
+    Created { target: PathBuf },
+    /// An existing file at `target` was removed to make room for a new subtitle,
+    /// optionally kept at `backup`
+    Removed {
+        target: PathBuf,
+        backup: Option<PathBuf>,
+    },
+}
+
+/// Appends entries to the journal of an output directory, creating it with the first entry so
+/// runs that change nothing leave no journal behind
+#[derive(Debug)]
+pub struct Journal {
+    path: PathBuf,
+    file: Mutex<Option<File>>,
+}
+
+impl Journal {
+    pub fn open(dir: &Path) -> Self {
+        Self {
+            path: dir.join(FILE_NAME),
+            file: Mutex::new(None),
+        }
+    }
+
+    pub fn record(&self, entry: &Entry) -> Result<(), SyncError> {
+        let mut line = serde_json::to_string(entry).expect("entry to serialize");
+        line.push('\n');
+        let mut file = self.file.lock().expect("journal lock");
+        let file = match &mut *file {
+            Some(file) => file,
+            None => file.insert(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.path)
+                    .map_err(SyncError::io(&self.path))?,
+            ),
+        };
+        file.write_all(line.as_bytes())
+            .map_err(SyncError::io(&self.path))
+    }
+}
+
+/// Whether something exists at the path, counting dangling symlinks
+fn exists(path: &Path) -> bool {
+    symlink_metadata(path).is_ok()
+}
+
+/// Reverses every operation in the journal of `dir`, newest first, then removes the journal.
+/// Entries whose targets no longer exist are skipped, so undoing twice is harmless
+pub fn undo(dir: &Path) -> Result<(), SyncError> {
+    let path = dir.join(FILE_NAME);
+    if !path.exists() {
+        println!("Nothing to undo in {}", dir.to_string_lossy());
+        return Ok(());
+    }
+
+    let file = File::open(&path).map_err(SyncError::io(&path))?;
+    let mut entries = Vec::new();
+    for (n, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(SyncError::io(&path))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str::<Entry>(&line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => eprintln!(
+                "{}",
+                format!("Skipping malformed journal line {}: {e}", n + 1).yellow()
+            ),
+        }
+    }
+
+    for entry in entries.iter().rev() {
+        match entry {
+            Entry::Created { target } => {
+                if !exists(target) {
+                    continue;
+                }
+                remove_file(target).map_err(SyncError::io(target))?;
+                println!("Removed {}", target.to_string_lossy());
+            }
+            Entry::Removed {
+                target,
+                backup: Some(backup),
+            } => {
+                if !exists(backup) || exists(target) {
+                    continue;
+                }
+                rename(backup, target).map_err(SyncError::io(target))?;
+                println!("Restored {}", target.to_string_lossy());
+            }
+            Entry::Removed {
+                target,
+                backup: None,
+            } => println!(
+                "{}",
+                format!(
+                    "Can't restore {}, it was replaced without a backup",
+                    target.to_string_lossy()
+                )
+                .yellow()
+            ),
+        }
+    }
+
+    remove_file(&path).map_err(SyncError::io(&path))?;
+    println!("{}", "Undo complete!".green().bold());
+    Ok(())
+}
@@ -0,0 +1,120 @@
+/// Common languages as ISO 639-1 code, ISO 639-2 codes, and English name
+const LANGUAGES: &[(&str, &[&str], &str)] = &[
+    ("ar", &["ara"], "arabic"),
+    ("bg", &["bul"], "bulgarian"),
+    ("cs", &["ces", "cze"], "czech"),
+    ("da", &["dan"], "danish"),
+    ("de", &["deu", "ger"], "german"),
+    ("el", &["ell", "gre"], "greek"),
+    ("en", &["eng"], "english"),
+    ("es", &["spa"], "spanish"),
+    ("fi", &["fin"], "finnish"),
+    ("fr", &["fra", "fre"], "french"),
+    ("he", &["heb"], "hebrew"),
+    ("hi", &["hin"], "hindi"),
+    ("hr", &["hrv"], "croatian"),
+    ("hu", &["hun"], "hungarian"),
+    ("id", &["ind"], "indonesian"),
+    ("it", &["ita"], "italian"),
+    ("ja", &["jpn"], "japanese"),
+    ("ko", &["kor"], "korean"),
+    ("nl", &["nld", "dut"], "dutch"),
+    ("no", &["nor"], "norwegian"),
+    ("pl", &["pol"], "polish"),
+    ("pt", &["por"], "portuguese"),
+    ("ro", &["ron", "rum"], "romanian"),
+    ("ru", &["rus"], "russian"),
+    ("sk", &["slk", "slo"], "slovak"),
+    ("sr", &["srp"], "serbian"),
+    ("sv", &["swe"], "swedish"),
+    ("th", &["tha"], "thai"),
+    ("tr", &["tur"], "turkish"),
+    ("uk", &["ukr"], "ukrainian"),
+    ("vi", &["vie"], "vietnamese"),
+    ("zh", &["zho", "chi"], "chinese"),
+];
+
+/// Whether `code` is a known ISO 639-1 or ISO 639-2 language code
+pub fn is_known(code: &str) -> bool {
+    let code = code.to_lowercase();
+    LANGUAGES
+        .iter()
+        .any(|(short, long, _)| *short == code || long.contains(&code.as_str()))
+}
+
+/// The ISO 639-1 code of a folder named after a language, by its English name or either code,
+/// such as `en` for `English`, `eng`, or `2_English`
+pub fn from_folder(name: &str) -> Option<String> {
+    let lower = name.to_lowercase();
+    lower
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .find_map(|t| {
+            LANGUAGES.iter().find(|(short, long, english)| {
+                *english == t || (lower == t && (*short == t || long.contains(&t)))
+            })
+        })
+        .map(|(code, _, _)| code.to_string())
+}
+
+/// Finds a language code in the segment before the extension of a subtitle file name,
+/// such as `en` in `Show.S01E01.en.srt`
+pub fn detect(file_name: &str) -> Option<String> {
+    let mut segments = file_name.rsplit('.');
+    // The extension can't be a language
+    segments.next();
+    segments
+        .next()
+        .filter(|s| s.len() <= 3 && is_known(s))
+        .map(|s| s.to_lowercase())
+}
+
+/// Flags describing a subtitle track that media servers recognise in file names
+const TAGS: &[&str] = &["forced", "sdh", "cc"];
+
+/// Finds the track flags (forced, sdh, cc) appearing as their own word in a file name
+pub fn tags(file_name: &str) -> Vec<&'static str> {
+    let lower = file_name.to_lowercase();
+    let tokens: Vec<_> = lower
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .collect();
+    TAGS.iter()
+        .copied()
+        .filter(|tag| tokens.contains(tag))
+        .collect()
+}
+
+/// Derives a `lang.tag` suffix for a subtitle from its file name, such as `en.forced` for
+/// `English_Forced.srt`. Language names are recognised anywhere in the name, while codes are only
+/// recognised as their own dot-separated segment
+pub fn suffix(file_name: &str) -> Option<String> {
+    let stem = file_name
+        .rsplit_once('.')
+        .map_or(file_name, |(stem, _)| stem)
+        .to_lowercase();
+    let tokens: Vec<_> = stem
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|t| !t.is_empty())
+        .collect();
+
+    let lang = detect(file_name).or_else(|| {
+        tokens.iter().find_map(|t| {
+            LANGUAGES
+                .iter()
+                .find(|(_, _, name)| name == t)
+                .map(|(code, _, _)| code.to_string())
+        })
+    });
+
+    let parts: Vec<_> = lang
+        .into_iter()
+        .chain(tags(file_name).into_iter().map(str::to_string))
+        .collect();
+
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join("."))
+    }
+}
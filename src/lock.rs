@@ -0,0 +1,187 @@
+use std::{
+    fs::{read_to_string, remove_file, File, OpenOptions, TryLockError},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    process,
+};
+
+use colored::Colorize;
+
+use crate::error::SyncError;
+
+/// Name of the lock file kept in the output directory while a run changes it
+pub const FILE_NAME: &str = ".subsync.lock";
+
+/// An advisory lock on an output directory, held until dropped so two runs never change the
+/// same output at once. The lock file, holding the ID of the process that took it, is removed
+/// when the lock is released. The operating system releases the lock when a run crashes, but
+/// leaves the file for the next run to lock in turn
+#[derive(Debug)]
+pub struct RunLock {
+    path: PathBuf,
+    file: Option<File>,
+}
+
+/// Whether the process `pid` is running on this machine
+fn running(pid: u32) -> bool {
+    #[cfg(target_os = "linux")]
+    let running = Path::new("/proc").join(pid.to_string()).exists();
+    #[cfg(all(unix, not(target_os = "linux")))]
+    let running = process::Command::new("kill")
+        .args(["-0", &pid.to_string()])
+        .stderr(process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+    #[cfg(windows)]
+    let running = process::Command::new("tasklist")
+        .args(["/FI", &format!("PID eq {pid}"), "/NH"])
+        .output()
+        .is_ok_and(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .split_whitespace()
+                .any(|word| word == pid.to_string())
+        });
+    running
+}
+
+/// Whether `file` is still the one at `path`, rather than one a run that just ended removed
+fn still_linked(file: &File, path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        match (file.metadata(), path.metadata()) {
+            (Ok(file), Ok(path)) => file.dev() == path.dev() && file.ino() == path.ino(),
+            _ => false,
+        }
+    }
+    // Without inode numbers, a file that's still there is taken to be the one locked
+    #[cfg(not(unix))]
+    {
+        let _ = file;
+        path.exists()
+    }
+}
+
+impl RunLock {
+    /// Locks `dir`, waiting for the run holding it to end with `wait` and failing otherwise.
+    /// With `force`, a lock held by a process that isn't running on this machine, such as one
+    /// left on a network share by a machine that crashed, is replaced. Folders that can't be
+    /// written to and filesystems without locks are left unlocked
+    pub fn acquire(dir: &Path, wait: bool, force: bool) -> Result<Self, SyncError> {
+        let path = dir.join(FILE_NAME);
+        let unlocked = || {
+            warn!(
+                "{}",
+                format!(
+                    "Cannot lock {}, another run could change it at the same time",
+                    dir.to_string_lossy()
+                )
+                .yellow()
+            );
+            Ok(Self {
+                path: path.clone(),
+                file: None,
+            })
+        };
+        let holder = || {
+            read_to_string(&path)
+                .ok()
+                .and_then(|pid| pid.trim().parse().ok())
+        };
+
+        loop {
+            let file = OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .read(true)
+                .write(true)
+                .open(&path);
+            let mut file = match file {
+                Ok(file) => file,
+                // Targets in a read-only folder fail on their own
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::PermissionDenied | io::ErrorKind::ReadOnlyFilesystem
+                    ) =>
+                {
+                    return unlocked()
+                }
+                Err(e) => return Err(SyncError::io(&path)(e)),
+            };
+
+            match file.try_lock() {
+                Ok(()) if force => {
+                    verbose!(
+                        "{} wasn't locked, there was nothing to force",
+                        dir.to_string_lossy()
+                    );
+                }
+                Ok(()) => {}
+                Err(TryLockError::WouldBlock) if force => {
+                    let described = match holder() {
+                        Some(pid) if running(pid) => {
+                            return Err(SyncError::StillLocked {
+                                dir: dir.to_path_buf(),
+                                holder: pid,
+                            })
+                        }
+                        Some(pid) => format!("process {pid}, which isn't running on this machine"),
+                        None => "an unknown process".to_string(),
+                    };
+                    warn!(
+                        "{}",
+                        format!(
+                            "Replacing the lock on {} held by {described}",
+                            dir.to_string_lossy()
+                        )
+                        .yellow()
+                    );
+                    remove_file(&path).map_err(SyncError::io(&path))?;
+                    return Self::acquire(dir, wait, false);
+                }
+                Err(TryLockError::WouldBlock) if wait => {
+                    info!(
+                        "Waiting for {} to finish changing {}",
+                        holder().map_or("another run".to_string(), |pid: u32| format!(
+                            "the run in process {pid}"
+                        )),
+                        dir.to_string_lossy()
+                    );
+                    file.lock().map_err(SyncError::io(&path))?;
+                }
+                Err(TryLockError::WouldBlock) => {
+                    return Err(SyncError::Locked {
+                        dir: dir.to_path_buf(),
+                        holder: holder(),
+                    })
+                }
+                Err(TryLockError::Error(e)) if e.kind() == io::ErrorKind::Unsupported => {
+                    return unlocked()
+                }
+                Err(TryLockError::Error(e)) => return Err(SyncError::io(&path)(e)),
+            }
+
+            // The run that held the lock removed the file on its way out, so another run may
+            // have locked a new one in its place
+            if !still_linked(&file, &path) {
+                continue;
+            }
+            file.set_len(0).map_err(SyncError::io(&path))?;
+            write!(file, "{}", process::id()).map_err(SyncError::io(&path))?;
+            return Ok(Self {
+                path,
+                file: Some(file),
+            });
+        }
+    }
+}
+
+impl Drop for RunLock {
+    /// Removes the lock file while the lock is still held, so no other run locks it in between
+    fn drop(&mut self) {
+        if self.file.is_some() {
+            let _ = remove_file(&self.path);
+        }
+    }
+}
@@ -1,37 +1,744 @@
 use std::{
-    collections::HashMap,
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    env,
     error::Error,
     fmt::Display,
-    fs::{copy, read_dir, remove_file},
-    os::unix::fs::{symlink, MetadataExt},
-    path::PathBuf,
+    fs::{
+        copy, create_dir_all, hard_link, metadata, read, read_dir, read_to_string, remove_file,
+        rename, symlink_metadata, write, DirEntry, File,
+    },
+    io::{self, IsTerminal},
+    path::{Path, PathBuf},
+    process::ExitCode,
+    sync::{Mutex, OnceLock},
+    time::{Duration, SystemTime},
 };
 
-use clap::Parser;
+use clap::{
+    error::ErrorKind, ArgAction, ArgGroup, Args, CommandFactory, Parser, Subcommand, ValueEnum,
+};
 use colored::Colorize;
 use humansize::DECIMAL;
-use inquire::{Select, Text};
+use inquire::{Confirm, InquireError, MultiSelect, Select, Text};
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+#[macro_use]
+mod output;
+
+mod archive;
+mod config;
+mod destination;
+mod embedded;
+mod encoding;
+mod error;
+mod external;
+mod fs_ops;
+mod interrupt;
+mod journal;
+mod lang;
+mod lock;
+mod manifest;
+mod matching;
+mod report;
+mod resume;
+mod rules;
+mod sidecar;
+mod srt;
+mod template;
+mod vtt;
+
+use error::SyncError;
 
 #[derive(Debug, Parser)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Cli {
-    /// Input directory, may either be a directory of directories for an entire season or just a single directory containing subtitle files
-    input: PathBuf,
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    sync: Option<SyncArgs>,
+    /// When to color output, auto disables it if NO_COLOR is set or stdout isn't a terminal
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto, global = true)]
+    color: ColorChoice,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum ColorChoice {
+    Auto,
+    Always,
+    Never,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Synchronize subtitles to media files, the default when no subcommand is given
+    Sync(Box<SyncArgs>),
+    /// Reverse the operations recorded in the journal of an output directory
+    Undo {
+        /// Output directory of the run to undo
+        output: PathBuf,
+    },
+    /// List the subtitles of a folder that are candidates for one media file, with the one each
+    /// strategy would select, without changing anything. Filters such as --ext, --keyword, and
+    /// --min-size apply as when synchronizing
+    List(ListArgs),
+}
+
+#[derive(Debug, Args)]
+struct ListArgs {
+    /// Folder of subtitles to list
+    sub_dir: PathBuf,
+    /// Media file the subtitles are candidates for
+    media_file: PathBuf,
+    /// Comma-separated list of subtitle extensions to consider as sources [default: srt]
+    #[arg(long = "ext", value_delimiter = ',', value_parser = parse_subtitle_ext)]
+    extensions: Option<Vec<String>>,
+    /// Subtitle file name keyword. Pass an empty string for no keyword
+    #[arg(short, long)]
+    keyword: Option<String>,
+    /// Regular expression subtitle file names, including their extension, must match.
+    /// Matching is case-sensitive unless the expression starts with `(?i)`. When given with
+    /// --keyword, subtitles must satisfy both
+    #[arg(long, value_name = "REGEX")]
+    keyword_regex: Option<Regex>,
+    /// Ignore subtitles smaller than this size, such as `500`, `10k`, or `1.5M`
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    min_size: Option<u64>,
+    /// Ignore subtitles larger than this size, such as a merged file of every language
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    max_size: Option<u64>,
+    /// Strategy the forced, sdh, and no-sdh strategies are listed as falling back to when none
+    /// are preferred [default: alphabetical]
+    #[arg(long, value_enum)]
+    fallback: Option<Fallback>,
+    /// Select from the other end of the sort order: the smallest file, the last by name, or the
+    /// shortest running
+    #[arg(long)]
+    reverse: bool,
+    /// Sort names the way people read them, numbers by value so `ep2` comes before `ep10` and
+    /// letters regardless of case and accents, instead of bytewise
+    #[arg(long)]
+    natural_sort: bool,
+}
+
+impl From<ListArgs> for SyncArgs {
+    /// Synchronization arguments filtering subtitles like `list`, so the config file and
+    /// candidate collection apply to both the same way
+    fn from(list: ListArgs) -> Self {
+        Self {
+            input: Some(list.sub_dir),
+            output: Some(list.media_file),
+            extensions: list.extensions,
+            keyword: list.keyword,
+            keyword_regex: list.keyword_regex,
+            min_size: list.min_size,
+            max_size: list.max_size,
+            fallback: list.fallback,
+            reverse: list.reverse,
+            natural_sort: list.natural_sort,
+            dry_run: true,
+            ..Self::default()
+        }
+    }
+}
+
+#[derive(Debug, Default, Args)]
+#[command(group(ArgGroup::new("keyword_filter").args(["keyword", "keyword_regex"]).multiple(true)))]
+struct SyncArgs {
+    /// Input directory, may either be a directory of directories for an entire season or just a single directory containing subtitle files.
+    /// Zip archives, given as the input or found in it, are read as the directories they contain
+    #[arg(required_unless_present_any = ["pairs", "manifest", "hash"])]
+    input: Option<PathBuf>,
     /// Output directory, must be the path where media files for the respective season/movie is.
     /// If a FILE is used instead, single mode is assumed
-    output: PathBuf,
+    #[arg(required_unless_present_any = ["pairs", "manifest", "hash", "source"])]
+    output: Option<PathBuf>,
+    /// Read explicit `subtitle<TAB>media` pairs, one per line, from a file or `-` for stdin
+    /// instead of scanning directories. Nothing is prompted for, so existing files are only
+    /// replaced with --yes, and the journal is kept in the current directory
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["input", "output", "multi_season", "all"]
+    )]
+    pairs: Option<PathBuf>,
+    /// Read `[[entry]]` tables pairing a folder of subtitles, or a single subtitle, with a media
+    /// file from a TOML file and synchronize exactly those, selecting among each folder's
+    /// subtitles as usual. Media in the optional `media-dir` without an entry is reported as
+    /// unmatched
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["input", "output", "pairs", "multi_season", "flatten"]
+    )]
+    manifest: Option<PathBuf>,
+    /// Print the OpenSubtitles hash and size of each media file in a directory, or of a single
+    /// media file, next to its stem instead of synchronizing anything, so matching subtitles can
+    /// be looked up elsewhere. Honors --recursive, --media-ext, and --follow-symlinks
+    #[arg(
+        long,
+        value_name = "PATH",
+        conflicts_with_all = ["input", "output", "pairs", "manifest"]
+    )]
+    hash: Option<PathBuf>,
+    /// Place this one subtitle for every media file instead of scanning an input directory, such
+    /// as a translation covering a whole season or a movie split across files. The only path
+    /// given is then the output, a directory or a single media file, and no strategy is needed
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = [
+            "output", "pairs", "manifest", "hash", "multi_season", "flatten", "all", "merge",
+            "reverse_link"
+        ]
+    )]
+    source: Option<PathBuf>,
     /// Whether to copy subtitles instead of symlinking them
     #[arg(short, long)]
     copy: bool,
+    /// Whether to hardlink subtitles instead of symlinking them. The subtitles must be on the same
+    /// filesystem as the media
+    #[arg(long, conflicts_with = "copy")]
+    hardlink: bool,
+    /// Fail instead of copying subtitles when symlinks can't be created, such as on FAT32, some
+    /// network mounts, or Windows without developer mode
+    #[arg(long, conflicts_with_all = ["copy", "hardlink"])]
+    no_fallback: bool,
     /// Whether to overwrite existing files
     #[arg(short, long)]
     overwrite: bool,
+    /// Leave targets alone that already provide the chosen subtitle: symlinks resolving to it,
+    /// or copies and hardlinks of the same size that are no older. Other existing targets are
+    /// only replaced with --overwrite
+    #[arg(long)]
+    skip_existing: bool,
+    /// Write `<target>.subsync.json` next to each placed subtitle, recording its source, the
+    /// strategy that selected it, the operation, and when it was placed. With --skip-existing,
+    /// targets whose sidecar names the same source at the same size are left alone
+    #[arg(long)]
+    sidecar: bool,
+    /// Comma-separated list of subtitle extensions to consider as sources [default: srt]
+    #[arg(long = "ext", value_delimiter = ',', value_parser = parse_subtitle_ext)]
+    extensions: Option<Vec<String>>,
+    /// Extension given to placed subtitles instead of their own, such as `srt`. Only the name
+    /// changes, the contents aren't converted to another format unless --convert is given
+    #[arg(long, value_name = "EXT")]
+    out_ext: Option<String>,
+    /// Convert WebVTT subtitles to SRT and back when --out-ext names the other format, instead
+    /// of only renaming them. Cue settings and styling SRT can't represent are dropped
+    #[arg(long, conflicts_with = "reverse_link")]
+    convert: bool,
+    /// Comma-separated list of media extensions to pair subtitles with in an output directory.
+    /// `none` also pairs files without an extension, such as `Show S01E01`, whose target is then
+    /// their whole name followed by the subtitle extension. Any other file without one counts as
+    /// media too, and names containing a dot always have an extension
+    /// [default: mkv,mp4,avi,m4v,mov]
+    #[arg(long, value_delimiter = ',')]
+    media_ext: Option<Vec<String>>,
+    /// Read the media from a file instead of scanning the output directory, such as the listing
+    /// of a remote server: one path per line or a JSON array of paths, relative to the output
+    /// unless absolute. Files without a media extension are skipped. The output then needn't
+    /// exist, so the plan can be made with --dry-run or placed elsewhere with --sub-out
+    #[arg(
+        long,
+        value_name = "FILE",
+        conflicts_with_all = [
+            "pairs", "manifest", "hash", "source", "multi_season", "prune", "reverse_link"
+        ]
+    )]
+    media_list: Option<PathBuf>,
+    /// Print the operations that would be performed without touching the filesystem
+    #[arg(long)]
+    dry_run: bool,
+    /// Wait for another run changing the same output to finish instead of failing. Runs lock
+    /// the directory their journal is kept in with a `.subsync.lock` file
+    #[arg(long)]
+    wait: bool,
+    /// Replace the lock of another run on the output when the process holding it isn't running
+    /// on this machine, such as one left on a network share by a machine that crashed. Locks of
+    /// runs that ended are released on their own
+    #[arg(long, conflicts_with = "wait")]
+    force_unlock: bool,
+    /// Print the subtitle the alphabetical, size, and duration strategies would each select for
+    /// every matched media file instead of placing anything, to compare them before a real run
+    #[arg(
+        long,
+        conflicts_with_all = ["all", "confirm", "prune", "pairs", "manifest", "source", "hash"]
+    )]
+    stats: bool,
+    /// Shift every subtitle timestamp by the given number of milliseconds, writing the result
+    /// instead of linking. Only supported for SRT subtitles
+    #[arg(long, value_name = "MS", allow_negative_numbers = true)]
+    shift: Option<i64>,
+    /// With --match-by episode, stitch the SRT subtitles selected for both episodes of media
+    /// covering two, such as `Show.S01E01E02`, into one file, offsetting the second episode's by
+    /// the end of the first's last cue. Otherwise only the first episode's subtitle is placed
+    #[arg(long, conflicts_with = "all")]
+    merge: bool,
+    /// In season mode, pair subtitle folders without an exact match to the most similar media
+    /// stem scoring at least THRESHOLD (0.0-1.0)
+    #[arg(
+        long,
+        value_name = "THRESHOLD",
+        num_args = 0..=1,
+        default_missing_value = "0.8",
+        value_parser = parse_threshold
+    )]
+    fuzzy: Option<f64>,
+    /// In season mode, pair subtitle folders without an exact match to the media stem whose
+    /// title is closest to theirs by --metric, scoring at least THRESHOLD (0.0-1.0). Years and
+    /// separators are ignored, so `The Matrix (1999)` scores 1.0 against `The.Matrix.1999`
+    #[arg(
+        long,
+        value_name = "THRESHOLD",
+        value_parser = parse_threshold,
+        conflicts_with = "fuzzy"
+    )]
+    similarity: Option<f64>,
+    /// The string distance --similarity scores titles by
+    #[arg(long, value_enum, requires = "similarity")]
+    metric: Option<Metric>,
+    /// In season mode, pair subtitle folders without an exact match to the only media stem
+    /// containing their name as a whole part, or for numbers right after an `E` or `x`, such as
+    /// `01` for `Show.S01E01.1080p` but not `Show.2014`. Folders several stems contain are
+    /// reported and left unmatched
+    #[arg(long)]
+    contains: bool,
+    /// How subtitle folders are paired with media files in season mode [default: name]
+    #[arg(long, value_enum)]
+    match_by: Option<MatchBy>,
+    /// In season mode, print the key each media stem and subtitle folder is compared by under
+    /// --ignore-case and --normalize, the episodes found in it, and what each folder was paired
+    /// with and how, to find out why names didn't pair
+    #[arg(long)]
+    explain: bool,
+    /// Let a shell command choose the subtitle for each media stem instead of matching folders.
+    /// It's run once per stem with `{"stem", "media", "input"}` as JSON on stdin and prints the
+    /// subtitle to place, relative to the input or absolute, which must be a file inside the
+    /// input. Printing nothing leaves the media unmatched
+    #[arg(
+        long,
+        value_name = "CMD",
+        conflicts_with_all = ["pairs", "manifest", "hash", "source", "stats"]
+    )]
+    match_command: Option<String>,
+    /// Scan nested directories of the output directory for media files
+    #[arg(short, long)]
+    recursive: bool,
+    /// Resolve symlinks while scanning the output for media and detecting the mode, so links to
+    /// directories are scanned, or treated as subtitle folders in the input, like the
+    /// directories they point at. By default a symlink only counts as a file named after the
+    /// link: linked media files are still found, but linked directories are neither descended
+    /// into nor taken for folders
+    #[arg(long)]
+    follow_symlinks: bool,
+    /// Consider files and folders whose name starts with a dot, such as the `._` files macOS
+    /// leaves on other filesystems, which are skipped by default in both the input and output
+    #[arg(long)]
+    include_hidden: bool,
+    /// Create symlinks using a path relative to the media directory instead of an absolute one
+    #[arg(long)]
+    relative: bool,
+    /// Place subtitles in this directory instead of next to their media, such as when the media
+    /// is on a read-only mount. Targets keep their names, and the journal is kept there as well
+    #[arg(long, value_name = "DIR", conflicts_with_all = ["reverse_link", "prune"])]
+    sub_out: Option<PathBuf>,
+    /// Recreate the folders of the media below the output directory inside --sub-out, so media
+    /// of the same name in different seasons don't share a target
+    #[arg(long, requires = "sub_out")]
+    mirror: bool,
+    /// Create the folder a subtitle is placed in when it doesn't exist, such as the media folder
+    /// of a manifest entry, instead of failing that media
+    #[arg(long)]
+    mkdir: bool,
+    /// Link each media file into the folder of the subtitle selected for it, under the media's
+    /// own name, instead of placing the subtitle next to the media. Selection is unchanged, and
+    /// existing files in the subtitle folder are replaced as with any other target
+    #[arg(
+        long,
+        conflicts_with_all = ["copy", "all", "shift", "merge", "template", "out_ext", "lang", "sidecar"]
+    )]
+    reverse_link: bool,
+    /// Subtitle selection strategy, skipping the strategy prompt
+    #[arg(short, long, value_enum)]
+    strategy: Option<SubtitleSelectionStrategy>,
+    /// Strategy used by the forced, sdh, and no-sdh strategies among all subtitles when none are
+    /// preferred, skipping the fallback prompt. Non-interactive runs default to alphabetical
+    #[arg(long, value_enum)]
+    fallback: Option<Fallback>,
+    /// Select from the other end of the sort order: the smallest file, the last by name, or the
+    /// shortest running, including when a preference strategy falls back. With the manual
+    /// strategy, lists the subtitles in the reverse of the chosen display sort instead
+    #[arg(long)]
+    reverse: bool,
+    /// Sort names the way people read them, numbers by value so `ep2` comes before `ep10` and
+    /// letters regardless of case and accents, instead of bytewise
+    #[arg(long)]
+    natural_sort: bool,
+    /// With the manual strategy, turn each selection into a pattern with wildcards for its
+    /// numbers, such as `Show.S*E*.English.srt`, and select the one subtitle matching it in later
+    /// folders without prompting
+    #[arg(long)]
+    learn: bool,
+    /// In season mode, select a subtitle for every folder first, then list the targets they
+    /// would be placed at and ask once before changing anything. Multi-season runs ask once per
+    /// season
+    #[arg(long, conflicts_with = "non_interactive")]
+    confirm: bool,
+    /// In season mode, list every subtitle folder with the media it was matched to, by name,
+    /// episode, or --fuzzy, and only synchronize the pairs left checked. The media of unchecked
+    /// pairs is reported as unmatched
+    #[arg(long, conflicts_with = "non_interactive")]
+    review: bool,
+    /// Subtitle file name keyword, skipping the keyword prompt. Pass an empty string for no keyword
+    #[arg(short, long)]
+    keyword: Option<String>,
+    /// Regular expression subtitle file names, including their extension, must match, skipping the
+    /// keyword prompt. Matching is case-sensitive unless the expression starts with `(?i)`.
+    /// When given with --keyword, subtitles must satisfy both
+    #[arg(long, value_name = "REGEX")]
+    keyword_regex: Option<Regex>,
+    /// Read `[[rule]]` tables from a TOML file, each giving the keyword or strategy to use for
+    /// the subtitle folders whose name matches its `folder` glob, such as `*S01E0?*`. Folders no
+    /// rule matches use the keyword and strategy of the run
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["pairs", "source", "hash"])]
+    rules: Option<PathBuf>,
+    /// Skip the media an earlier interrupted run with --resume finished, and record what this
+    /// run finishes in `.subsync-resume` in the output directory so it can be resumed in turn.
+    /// The file is removed once a run has gone through every folder
+    #[arg(long, conflicts_with_all = ["pairs", "source", "hash"])]
+    resume: bool,
+    /// Never prompt, requiring --strategy (unless --all is used) and --keyword or --keyword-regex.
+    /// Ambiguous matches are left unmatched
+    #[arg(long, requires = "keyword_filter")]
+    non_interactive: bool,
+    /// Give up on a prompt when no key is pressed within SECS seconds, using its default: the
+    /// first strategy listed, no keyword, skipping the folder when selecting manually, and not
+    /// replacing or removing anything. Later prompts then use their default straight away.
+    /// Irrelevant with --non-interactive, which never prompts
+    #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    prompt_timeout: Option<u64>,
+    /// Write a JSON summary of every operation to the given path
+    #[arg(long, value_name = "PATH")]
+    report: Option<PathBuf>,
+    /// Language code inserted before the subtitle extension, such as `en` for `Show.en.srt`.
+    /// Defaults to a code detected in the source subtitle's name
+    #[arg(long, value_name = "CODE")]
+    lang: Option<String>,
+    /// Format of subtitle targets relative to their media's directory, using the placeholders
+    /// `{stem}`, `{lang}`, `{ext}`, and `{tag}`, such as `Subs/{stem}.{lang}.{tag}.{ext}`. The dot
+    /// before an empty placeholder is dropped. Defaults to `{stem}.{lang}.{ext}`, with tags after
+    /// the language when using --all
+    #[arg(long, value_name = "FORMAT", value_parser = template::Template::parse)]
+    template: Option<template::Template>,
+    /// Place every subtitle in the folder instead of selecting one, tagging each with the
+    /// language and flags (forced, sdh, cc) found in its name. --lang only applies to subtitles
+    /// without a detected language
+    #[arg(short, long, conflicts_with = "strategy")]
+    all: bool,
+    /// Treat the folders directly inside each subtitle folder as languages, such as
+    /// `Subs/English/` or `Subs/spa/`, selecting one subtitle from each and tagging it with that
+    /// language's code. Folders not named after a known language are skipped
+    #[arg(long, conflicts_with_all = ["all", "lang", "merge", "reverse_link"])]
+    lang_dirs: bool,
+    /// Detect the encoding of copied subtitles and convert them to UTF-8
+    #[arg(long, requires = "copy")]
+    transcode: bool,
+    /// Strip the byte order mark from copied text subtitles and convert their line endings,
+    /// leaving formats that may be binary, such as VobSub `.sub`, untouched
+    #[arg(long, requires = "copy")]
+    normalize_text: bool,
+    /// Line endings written by --normalize-text [default: lf]
+    #[arg(long, value_enum, requires = "normalize_text")]
+    eol: Option<Eol>,
+    /// Compare the SHA-256 checksums of each copy and its source, removing copies that differ
+    #[arg(
+        long,
+        requires = "copy",
+        conflicts_with_all = ["transcode", "normalize_text"]
+    )]
+    verify: bool,
+    /// Hardlink copies whose contents match an earlier copy to it instead of copying them again,
+    /// such as forced subtitles shared by a season. Falls back to a copy when they can't be
+    /// hardlinked
+    #[arg(long, requires = "copy")]
+    dedup: bool,
+    /// Give copies the modification time of their source instead of the time they were made.
+    /// Links already share it
+    #[arg(long)]
+    preserve_mtime: bool,
+    /// Warn about media that already has embedded subtitle streams, found with ffprobe. Checking
+    /// is skipped with a warning when ffprobe isn't installed
+    #[arg(long)]
+    check_embedded: bool,
+    /// Like --check-embedded, but leave media with embedded subtitles without an external one
+    #[arg(long)]
+    skip_embedded: bool,
+    /// Replace existing files without asking for confirmation. Without this, --non-interactive
+    /// refuses to replace files
+    #[arg(short, long)]
+    yes: bool,
+    /// Remove subtitles in the output that no media file's stem matches anymore, and dangling
+    /// subtitle symlinks, after asking unless --yes is given
+    #[arg(long)]
+    prune: bool,
+    /// When overwriting, rename existing files to `<file>.bak` (or `<file>.bak.N`) instead of
+    /// deleting them
+    #[arg(short, long, requires = "overwrite")]
+    backup: bool,
+    /// Number of folders to process at once in non-interactive season mode.
+    /// Defaults to the number of CPUs
+    #[arg(short, long, value_name = "N", value_parser = clap::value_parser!(u16).range(1..))]
+    jobs: Option<u16>,
+    /// Stop after synchronizing N folders in season mode, such as to try out a new layout. The
+    /// folders left over are listed separately from the unmatched ones
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    limit: Option<u64>,
+    /// Ignore subtitles smaller than this size, such as `500`, `10k`, or `1.5M`
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    min_size: Option<u64>,
+    /// Ignore subtitles larger than this size, such as a merged file of every language
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    max_size: Option<u64>,
+    /// Consider symlinked subtitles as sources. By default they're skipped so links made by an
+    /// earlier run aren't picked up again
+    #[arg(long)]
+    include_symlinks: bool,
+    /// Also consider subtitles in the folders of each subtitle folder, up to N levels below it,
+    /// such as `Forced/episode.srt` with 1. Subtitles in subfolders are listed with their path
+    /// relative to the subtitle folder [default: 0]
+    #[arg(long, value_name = "N")]
+    sub_depth: Option<usize>,
+    /// Check that chosen SRT subtitles are well-formed before placing them, either failing the
+    /// folder or only warning when they aren't. Other subtitle formats aren't checked
+    #[arg(
+        long,
+        value_enum,
+        value_name = "SEVERITY",
+        num_args = 0..=1,
+        default_missing_value = "error"
+    )]
+    validate: Option<Severity>,
+
+    /// Treat the input and output as folders of seasons, pairing them by name or season number
+    /// and matching the folders within each pair as in season mode
+    #[arg(long)]
+    multi_season: bool,
+
+    /// Leave out specials seasons in multi-season mode: folders named `Specials` or numbered
+    /// season 0, such as `Season 00`
+    #[arg(long, requires = "multi_season", overrides_with = "include_specials")]
+    exclude_specials: bool,
+
+    /// Synchronize specials seasons like any other, overriding `exclude-specials` in the config
+    /// file. This is the default
+    #[arg(long, requires = "multi_season", overrides_with = "exclude_specials")]
+    include_specials: bool,
+
+    /// Match the subtitle files directly inside the input directory against the media, by stem
+    /// or episode number, instead of expecting a folder per episode. Used automatically when the
+    /// input has no subdirectories and the output is a directory
+    #[arg(long, conflicts_with = "multi_season")]
+    flatten: bool,
+
+    /// Match folder names to media stems and season folders ignoring case, such as `show` to
+    /// `Show.mkv`. An exact match is still preferred
+    #[arg(long)]
+    ignore_case: bool,
+
+    /// Compare names ignoring case and treating runs of spaces, dots, underscores, and dashes
+    /// alike, so `Show_01` matches `Show - 01 - Pilot.mkv`. Output names are unaffected
+    #[arg(
+        long,
+        value_enum,
+        value_name = "RULES",
+        num_args = 0..=1,
+        default_missing_value = "titles"
+    )]
+    normalize: Option<Normalize>,
+
+    /// Force season or single mode. `auto` picks multi-season mode with --multi-season, flat
+    /// mode when the output is a directory and --flatten is given or the input has no
+    /// subdirectories, season mode when the output is a directory or the input only holds
+    /// directories, and single mode otherwise
+    #[arg(long, value_enum, default_value_t = ModeChoice::Auto)]
+    mode: ModeChoice,
+
+    /// Print more detail about each folder, -vv for debugging output
+    #[arg(short, long, action = ArgAction::Count, conflicts_with = "quiet")]
+    verbose: u8,
+
+    /// Only print errors and the final summary
+    #[arg(short, long)]
+    quiet: bool,
+
+    /// Only print errors and the final result line, such as `Done!`, leaving out the counts and
+    /// lists --quiet still prints
+    #[arg(long, conflicts_with = "verbose")]
+    quiet_summary_only: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+enum ModeChoice {
+    /// Detect the mode from the input and output
+    #[default]
+    Auto,
+    /// Match each folder of the input against the media in the output
+    Season,
+    /// Place a subtitle from the input folder next to the output file
+    Single,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Severity {
+    /// Print the problems and continue
+    Warn,
+    /// Fail the folder
+    Error,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Normalize {
+    /// Only unify case and separators
+    Separators,
+    /// Also drop anything after the episode number, such as an episode title
+    Titles,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Metric {
+    /// Jaro-Winkler similarity, favoring titles that start alike
+    #[default]
+    JaroWinkler,
+    /// Levenshtein edit distance relative to the longer title
+    Levenshtein,
+    /// Shared character pairs, as used by --fuzzy
+    Bigram,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Eol {
+    /// Unix line endings, `\n`
+    Lf,
+    /// Windows line endings, `\r\n`
+    Crlf,
+}
+
+impl Eol {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::Crlf => "\r\n",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum MatchBy {
+    /// Folder names must equal the media file stem, or be a run of its dot-separated parts
+    /// found in no other stem, such as `S01E01` for `Show.S01E01.1080p`
+    Name,
+    /// Folders not named after a media file are paired with one by the season and episode
+    /// numbers in their names
+    Episode,
+}
+
+/// Parses a subtitle extension, refusing [`NO_EXTENSION`] since subtitles are placed under
+/// their own extension
+fn parse_subtitle_ext(s: &str) -> Result<String, String> {
+    if s.trim()
+        .trim_start_matches('.')
+        .eq_ignore_ascii_case(NO_EXTENSION)
+    {
+        Err(format!("`{NO_EXTENSION}` is only accepted by --media-ext"))
+    } else {
+        Ok(s.to_string())
+    }
+}
+
+fn parse_threshold(s: &str) -> Result<f64, String> {
+    let threshold: f64 = s.parse().map_err(|_| format!("`{s}` isn't a number"))?;
+    if (0.0..=1.0).contains(&threshold) {
+        Ok(threshold)
+    } else {
+        Err("threshold must be between 0.0 and 1.0".to_string())
+    }
+}
+
+/// Parses a byte count with an optional decimal `k`, `M`, or `G` suffix
+fn parse_size(s: &str) -> Result<u64, String> {
+    let lower = s.trim().to_lowercase();
+    let lower = lower.strip_suffix('b').unwrap_or(&lower);
+    let (number, multiplier) = match lower.char_indices().last() {
+        Some((i, 'k')) => (&lower[..i], 1e3),
+        Some((i, 'm')) => (&lower[..i], 1e6),
+        Some((i, 'g')) => (&lower[..i], 1e9),
+        _ => (lower, 1.0),
+    };
+    let number: f64 = number
+        .trim()
+        .parse()
+        .map_err(|_| format!("`{s}` isn't a size like `500`, `10k`, or `1.5M`"))?;
+    if number < 0.0 {
+        return Err("size can't be negative".to_string());
+    }
+    Ok((number * multiplier) as u64)
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
 enum SubtitleSelectionStrategy {
+    /// First file by name
     Alphabetical,
+    /// Largest file
     Size,
+    /// Prompt for each folder
     Manual,
+    /// Forced subtitles, for foreign dialogue only
+    Forced,
+    /// SDH or closed caption subtitles
+    Sdh,
+    /// Subtitles that aren't SDH or closed captions
+    NoSdh,
+    /// SRT file whose cues run the longest
+    Duration,
+    /// Most recently modified file
+    Newest,
+}
+
+impl SubtitleSelectionStrategy {
+    /// Name of the strategy as given to --strategy
+    fn name(self) -> String {
+        self.to_possible_value()
+            .expect("no skipped strategies")
+            .get_name()
+            .to_string()
+    }
+
+    /// Whether the strategy prefers certain tracks, falling back to another strategy among the
+    /// rest when none are present
+    fn is_preference(self) -> bool {
+        matches!(self, Self::Forced | Self::Sdh | Self::NoSdh)
+    }
+
+    /// Whether a preference strategy favours the named subtitle
+    fn prefers(self, name: &str) -> bool {
+        let tags = lang::tags(name);
+        let sdh = tags.contains(&"sdh") || tags.contains(&"cc");
+        match self {
+            Self::Forced => tags.contains(&"forced"),
+            Self::Sdh => sdh,
+            Self::NoSdh => !sdh,
+            _ => false,
+        }
+    }
 }
 
 impl Display for SubtitleSelectionStrategy {
@@ -40,292 +747,4053 @@ impl Display for SubtitleSelectionStrategy {
             Self::Alphabetical => "First alphabetical",
             Self::Size => "Largest",
             Self::Manual => "Manually select",
+            Self::Forced => "Prefer forced",
+            Self::Sdh => "Prefer SDH",
+            Self::NoSdh => "Avoid SDH",
+            Self::Duration => "Longest running",
+            Self::Newest => "Most recently modified",
+        })
+    }
+}
+
+/// Strategy used by preference strategies when no subtitle is preferred
+#[derive(Debug, Clone, Copy, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Fallback {
+    /// First file by name
+    Alphabetical,
+    /// Largest file
+    Size,
+}
+
+impl From<Fallback> for SubtitleSelectionStrategy {
+    fn from(value: Fallback) -> Self {
+        match value {
+            Fallback::Alphabetical => Self::Alphabetical,
+            Fallback::Size => Self::Size,
+        }
+    }
+}
+
+/// How a chosen subtitle is placed next to its media file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LinkMode {
+    Symlink,
+    Hardlink,
+    Copy,
+}
+
+impl LinkMode {
+    fn verb(self) -> &'static str {
+        match self {
+            Self::Symlink => "symlink",
+            Self::Hardlink => "hardlink",
+            Self::Copy => "copy",
+        }
+    }
+}
+
+impl Display for LinkMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Symlink => "symlinking",
+            Self::Hardlink => "hardlinking",
+            Self::Copy => "copying",
         })
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "lowercase")]
 enum Mode {
     Season,
     Single,
+    #[serde(rename = "multi-season")]
+    MultiSeason,
+    Flat,
+    Pairs,
+    Manifest,
+    Source,
+}
+
+impl Display for Mode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Season => "season",
+            Self::Single => "single",
+            Self::MultiSeason => "multi-season",
+            Self::Flat => "flat",
+            Self::Pairs => "pairs",
+            Self::Manifest => "manifest",
+            Self::Source => "source",
+        })
+    }
 }
 
-fn main() -> Result<(), Box<dyn Error>> {
+/// Exit code of runs that left media unmatched or failed to place some subtitles. Fatal errors
+/// exit with 1, invalid arguments with 2, and interrupted runs with [`interrupt::EXIT_CODE`]
+const PARTIAL: u8 = 3;
+
+fn main() -> Result<ExitCode, Box<dyn Error>> {
     let cli = Cli::parse();
 
-    let mode = if cli.output.is_dir()
-        || read_dir(&cli.input)?
+    match cli.color {
+        ColorChoice::Always => colored::control::set_override(true),
+        ColorChoice::Never => colored::control::set_override(false),
+        ColorChoice::Auto => {
+            if env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) || !io::stdout().is_terminal()
+            {
+                colored::control::set_override(false);
+            }
+        }
+    }
+
+    interrupt::install()?;
+
+    match cli.command {
+        Some(Command::Undo { output }) => {
+            let _lock = output
+                .is_dir()
+                .then(|| lock::RunLock::acquire(&output, false, false))
+                .transpose()?;
+            journal::undo(&output)?;
+            Ok(ExitCode::SUCCESS)
+        }
+        Some(Command::Sync(args)) => sync(*args),
+        Some(Command::List(args)) => list(args),
+        None => sync(cli.sync.expect("sync arguments without a subcommand")),
+    }
+}
+
+/// Sets the output level and applies the config file to `cli`, then checks the arguments the
+/// config may have set
+fn prepare(cli: &mut SyncArgs) -> Result<(), Box<dyn Error>> {
+    output::set_level(match (cli.quiet, cli.verbose) {
+        _ if cli.quiet_summary_only => output::Level::ResultOnly,
+        (true, _) => output::Level::Quiet,
+        (false, 0) => output::Level::Normal,
+        (false, 1) => output::Level::Verbose,
+        (false, _) => output::Level::Debug,
+    });
+
+    match config::Config::load() {
+        Ok(config) => config.apply(cli),
+        Err(e) => {
+            eprintln!("{}", e.to_string().red().bold());
+            return Err(e.into());
+        }
+    }
+    output::set_prompt_timeout(cli.prompt_timeout.map(Duration::from_secs));
+
+    if let (Some(min_size), Some(max_size)) = (cli.min_size, cli.max_size) {
+        if min_size > max_size {
+            Cli::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    "--min-size can't be larger than --max-size",
+                )
+                .exit();
+        }
+    }
+    Ok(())
+}
+
+/// Prints the candidates of a subtitle folder for a media file, each detailed, then the one
+/// every strategy would select
+fn list(args: ListArgs) -> Result<ExitCode, Box<dyn Error>> {
+    let (sub_dir, media) = (args.sub_dir.clone(), args.media_file.clone());
+    let mut cli = SyncArgs::from(args);
+    prepare(&mut cli)?;
+
+    let extensions = normalize_extensions(cli.extensions.as_deref().unwrap_or(&["srt".into()]));
+    let options = SyncOptions {
+        required_text: cli
+            .keyword
+            .iter()
+            .filter(|k| !k.is_empty())
+            .map(|k| k.to_lowercase())
+            .collect(),
+        ..SyncOptions::new(&cli, LinkMode::Symlink, extensions, Path::new("."))
+    };
+    let candidates = collect_candidates(&sub_dir, &options).and_then(|candidates| {
+        if candidates.is_empty() {
+            Err(SyncError::NoSubtitles {
+                dir: sub_dir.clone(),
+                min_size: options.min_size,
+                max_size: options.max_size,
+            })
+        } else {
+            Ok(candidates)
+        }
+    });
+    let mut candidates = match candidates {
+        Ok(candidates) => candidates,
+        Err(e) => {
+            eprintln!("{}", e.to_string().red().bold());
+            return Err(e.into());
+        }
+    };
+    candidates.sort_unstable_by(|a, b| options.compare_candidates(a, b));
+
+    println!(
+        "{}",
+        format!(
+            "Candidates in {} for {}:",
+            sub_dir.to_string_lossy(),
+            media_stem(&media)?
+        )
+        .bold()
+    );
+    for candidate in &candidates {
+        println!(" - {candidate:#}");
+    }
+
+    let listed = |c: Option<&Candidate>| c.map_or("-".to_string(), |c| c.listed.clone());
+    let [alphabetical, size, duration] = strategy_picks(&candidates, &options);
+    let newest = candidates
+        .iter()
+        .filter(|c| c.modified.is_some())
+        .max_by_key(|c| c.modified);
+    let mut picks = vec![
+        (
+            SubtitleSelectionStrategy::Alphabetical,
+            listed(alphabetical),
+        ),
+        (SubtitleSelectionStrategy::Size, listed(size)),
+        (SubtitleSelectionStrategy::Duration, listed(duration)),
+        (SubtitleSelectionStrategy::Newest, listed(newest)),
+    ];
+    let fallback: SubtitleSelectionStrategy = cli
+        .fallback
+        .map_or(SubtitleSelectionStrategy::Alphabetical, Into::into);
+    for preference in [
+        SubtitleSelectionStrategy::Forced,
+        SubtitleSelectionStrategy::Sdh,
+        SubtitleSelectionStrategy::NoSdh,
+    ] {
+        let preferred = candidates.iter().find(|c| preference.prefers(&c.name));
+        let pick = match preferred {
+            Some(candidate) => candidate.listed.clone(),
+            None => format!("none preferred, falls back to {}", fallback.name()),
+        };
+        picks.push((preference, pick));
+    }
+
+    let width = picks.iter().map(|(s, _)| s.name().len()).max().unwrap_or(0);
+    println!("{}", "Selected by:".bold());
+    for (strategy, pick) in picks {
+        println!(" {:<width$}  {pick}", strategy.name());
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+fn sync(mut cli: SyncArgs) -> Result<ExitCode, Box<dyn Error>> {
+    prepare(&mut cli)?;
+
+    let link_mode = if cli.copy {
+        LinkMode::Copy
+    } else if cli.hardlink {
+        LinkMode::Hardlink
+    } else {
+        LinkMode::Symlink
+    };
+
+    let extensions = normalize_extensions(cli.extensions.as_deref().unwrap_or(&["srt".into()]));
+    if let Some(out_ext) = &cli.out_ext {
+        let out_ext = out_ext.trim_start_matches('.').to_lowercase();
+        if extensions
+            .iter()
+            .any(|e| *e != out_ext && !(cli.convert && converts(e, &out_ext)))
+        {
+            warn!(
+                "{}",
+                format!(
+                    "Renaming subtitles to .{out_ext} without converting them, players may not \
+                     read subtitles in another format"
+                )
+                .yellow()
+            );
+        }
+    }
+
+    let manifest = match cli.manifest.as_deref().map(manifest::Manifest::load) {
+        Some(Err(e)) => {
+            eprintln!("{}", e.to_string().red().bold());
+            return Err(e.into());
+        }
+        manifest => manifest.transpose()?,
+    };
+
+    let rules = match cli.rules.as_deref().map(rules::Rules::load) {
+        Some(Err(e)) => {
+            eprintln!("{}", e.to_string().red().bold());
+            return Err(e.into());
+        }
+        rules => rules.transpose()?,
+    };
+
+    let media_extensions = normalize_extensions(cli.media_ext.as_deref().unwrap_or(&[
+        "mkv".into(),
+        "mp4".into(),
+        "avi".into(),
+        "m4v".into(),
+        "mov".into(),
+    ]));
+
+    if let Some(path) = &cli.hash {
+        let walk = destination::Walk {
+            recursive: cli.recursive,
+            follow_symlinks: cli.follow_symlinks,
+            include_hidden: cli.include_hidden,
+        };
+        return print_hashes(path, &media_extensions, walk);
+    }
+
+    if let (Some(source), Some(output)) = (&cli.source, &cli.input) {
+        return sync_source(
+            source,
+            output,
+            &cli,
+            link_mode,
+            extensions,
+            &media_extensions,
+        );
+    }
+
+    let (input, output) = match (&cli.input, &cli.output, &cli.pairs, &cli.manifest) {
+        (_, _, Some(pairs), _) => return sync_pairs(pairs, &cli, link_mode, extensions),
+        (_, _, _, Some(path)) => {
+            let dir = path
+                .parent()
+                .filter(|p| !p.as_os_str().is_empty())
+                .unwrap_or(Path::new("."))
+                .to_path_buf();
+            let output = manifest
+                .as_ref()
+                .and_then(|m| m.media_dir.clone())
+                .unwrap_or_else(|| dir.clone());
+            (dir, output)
+        }
+        (Some(input), Some(output), None, None) => (input.clone(), output.clone()),
+        _ => unreachable!("input and output are required without --pairs or --manifest"),
+    };
+    // Listed media is in the output directory, even where it can't be read
+    let output_dir = cli.media_list.is_some() || output.is_dir();
+
+    if cli.non_interactive
+        && (matches!(cli.strategy, Some(SubtitleSelectionStrategy::Manual))
+            || rules.as_ref().is_some_and(rules::Rules::selects_manually))
+    {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "the manual strategy requires prompts and can't be used with --non-interactive",
+            )
+            .exit();
+    }
+    if cli.non_interactive
+        && cli.strategy.is_none()
+        && !cli.all
+        && !cli.stats
+        && cli.match_command.is_none()
+    {
+        Cli::command()
+            .error(
+                ErrorKind::MissingRequiredArgument,
+                "--non-interactive requires --strategy unless --all is used",
+            )
+            .exit();
+    }
+
+    if cli.mode != ModeChoice::Auto && (cli.multi_season || cli.flatten) {
+        Cli::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--mode season and --mode single can't be combined with --multi-season or --flatten",
+            )
+            .exit();
+    }
+    if cli.mode == ModeChoice::Single && output_dir {
+        Cli::command()
+            .error(
+                ErrorKind::InvalidValue,
+                "--mode single requires an output file",
+            )
+            .exit();
+    }
+
+    // Extracted subtitles are removed when this is dropped at the end of the run
+    let staging = archive::Staging::new();
+    let input = if input.is_file() && archive::is_archive(&input) {
+        info!("Reading subtitles from {}", input.to_string_lossy().bold());
+        staging.extract(&input, &extensions)?
+    } else {
+        input
+    };
+
+    let walk = destination::Walk {
+        recursive: cli.recursive,
+        follow_symlinks: cli.follow_symlinks,
+        include_hidden: cli.include_hidden,
+    };
+    let mode = if manifest.is_some() {
+        Mode::Manifest
+    } else if cli.mode == ModeChoice::Season {
+        Mode::Season
+    } else if cli.mode == ModeChoice::Single {
+        Mode::Single
+    } else if cli.multi_season {
+        if !output.is_dir() {
+            Cli::command()
+                .error(
+                    ErrorKind::InvalidValue,
+                    "--multi-season requires an output directory",
+                )
+                .exit();
+        }
+        Mode::MultiSeason
+    } else if output_dir
+        && (cli.flatten
+            || read_dir(&input)?
+                .flatten()
+                .filter(|i| walk.lists(&i.path()))
+                .all(|i| !is_folder(&i, cli.follow_symlinks)))
+    {
+        Mode::Flat
+    } else if output_dir
+        || read_dir(&input)?
             .flatten()
-            .all(|i| i.file_type().expect("file type to be readable").is_dir())
+            .filter(|i| walk.lists(&i.path()))
+            .all(|i| is_folder(&i, cli.follow_symlinks))
     {
-        println!("Using {} mode", "season".bold());
         Mode::Season
     } else {
-        println!("Using {} mode", "single".bold());
         Mode::Single
     };
+    info!("Using {} mode", mode.to_string().bold());
+
+    if cli.dry_run {
+        info!("{}", "Dry run, no files will be changed".cyan());
+    }
 
     if cli.overwrite {
-        println!("{}", "Overwrite mode enabled!".red());
+        info!("{}", "Overwrite mode enabled!".red());
     }
 
-    println!(
-        "In {} mode",
-        if cli.copy { "copying" } else { "symlinking" }.bold()
-    );
+    if cli.skip_existing {
+        info!("Skipping targets that are already up to date");
+    }
 
-    println!("Reading destination...");
+    if cli.all {
+        info!("Placing {} subtitles in each folder", "all".bold());
+    }
 
-    let mut destination_stems: HashMap<String, PathBuf> = if cli.output.is_dir() {
-        read_dir(&cli.output)?
-            .flatten()
-            .filter(|de| !de.path().is_dir() && !de.path().extension().is_some_and(|e| e == "srt"))
-            .map(|i| {
-                (
-                    i.path()
-                        .file_stem()
-                        .unwrap_or(&i.file_name())
-                        .to_string_lossy()
-                        .to_string(),
-                    i.path(),
-                )
-            })
-            .collect()
+    if let Some(lang) = &cli.lang {
+        if !lang::is_known(lang) {
+            info!(
+                "{}",
+                format!("Unknown language code {lang}, using it anyway").yellow()
+            );
+        }
+    }
+
+    if cli.merge && cli.match_by != Some(MatchBy::Episode) {
+        warn!(
+            "{}",
+            "--merge only applies with --match-by episode, ignoring it".yellow()
+        );
+    }
+
+    if let Some(shift) = cli.shift {
+        info!(
+            "Shifting subtitle timing by {} ms",
+            shift.to_string().bold()
+        );
     } else {
-        [(
-            cli.output
-                .file_stem()
-                .unwrap_or(cli.output.file_name().expect("file name"))
-                .to_string_lossy()
-                .to_string(),
-            cli.output.clone(),
-        )]
-        .into_iter()
-        .collect()
+        info!("In {} mode", link_mode.to_string().bold());
+    }
+
+    info!("Reading destination...");
+
+    let mut seasons = if let Mode::MultiSeason = mode {
+        pair_seasons(
+            &input,
+            &output,
+            &media_extensions,
+            walk,
+            &staging,
+            &extensions,
+            &cli,
+        )?
+    } else if let Some(manifest) = &manifest {
+        let mut stems = match &manifest.media_dir {
+            Some(dir) => destination::scan(dir, &media_extensions, walk)?,
+            None => HashMap::new(),
+        };
+        for entry in &manifest.entries {
+            let stem = media_stem(&entry.media)?;
+            let files = stems.entry(stem).or_default();
+            if !files.contains(&entry.media) {
+                files.push(entry.media.clone());
+            }
+        }
+        vec![Season {
+            name: None,
+            input: None,
+            stems,
+        }]
+    } else if output_dir {
+        let stems = match &cli.media_list {
+            Some(list) => destination::listed(list, &output, &media_extensions)?,
+            None => destination::scan(&output, &media_extensions, walk)?,
+        };
+        vec![Season {
+            name: None,
+            input: Some(input.clone()),
+            stems,
+        }]
+    } else {
+        let stems = [(media_stem(&output)?, vec![output.clone()])]
+            .into_iter()
+            .collect();
+        vec![Season {
+            name: None,
+            input: Some(input.clone()),
+            stems,
+        }]
     };
 
-    if destination_stems.is_empty() {
+    let num_stems: usize = seasons.iter().map(|s| s.stems.len()).sum();
+    if num_stems == 0 {
         eprintln!("{}", "No destination files!".red().bold());
         return Err("No files".into());
     }
 
-    let num_stems = destination_stems.len();
-
-    println!(
+    info!(
         "Destination read with {} {}",
         num_stems.to_string().bold(),
-        if destination_stems.len() > 1 {
-            "entires"
-        } else {
-            "entry"
-        }
+        if num_stems > 1 { "entires" } else { "entry" }
     );
 
-    let strategy = Select::new(
-        "Select a strategy:",
-        vec![
+    let strategy = match cli.strategy {
+        Some(strategy) => strategy,
+        // Every subtitle is placed, none is, or the match command chooses the one that is, so
+        // the strategy is irrelevant
+        None if cli.all || cli.stats || cli.match_command.is_some() => {
+            SubtitleSelectionStrategy::Alphabetical
+        }
+        None => output::prompt(
+            "Select a strategy:",
             SubtitleSelectionStrategy::Alphabetical,
-            SubtitleSelectionStrategy::Size,
-            SubtitleSelectionStrategy::Manual,
-        ],
-    )
-    .prompt()?;
+            |message| {
+                Select::new(
+                    message,
+                    vec![
+                        SubtitleSelectionStrategy::Alphabetical,
+                        SubtitleSelectionStrategy::Size,
+                        SubtitleSelectionStrategy::Manual,
+                        SubtitleSelectionStrategy::Forced,
+                        SubtitleSelectionStrategy::Sdh,
+                        SubtitleSelectionStrategy::NoSdh,
+                        SubtitleSelectionStrategy::Duration,
+                        SubtitleSelectionStrategy::Newest,
+                    ],
+                )
+                .prompt()
+            },
+        )?,
+    };
 
     let sort_strat = if matches!(strategy, SubtitleSelectionStrategy::Manual) {
-        match Select::new("Select a display sort type: ", vec!["Name", "Size"]).prompt()? {
+        match output::prompt("Select a display sort type:", "Name", |message| {
+            Select::new(message, vec!["Name", "Size", "Modified"]).prompt()
+        })? {
             "Name" => SubtitleSelectionStrategy::Alphabetical,
             "Size" => SubtitleSelectionStrategy::Size,
+            "Modified" => SubtitleSelectionStrategy::Newest,
             _ => unreachable!(),
         }
+    } else if strategy.is_preference() {
+        match cli.fallback {
+            Some(fallback) => fallback.into(),
+            None if cli.non_interactive => SubtitleSelectionStrategy::Alphabetical,
+            None => output::prompt(
+                "Select a fallback strategy:",
+                SubtitleSelectionStrategy::Alphabetical,
+                |message| {
+                    Select::new(
+                        message,
+                        vec![
+                            SubtitleSelectionStrategy::Alphabetical,
+                            SubtitleSelectionStrategy::Size,
+                        ],
+                    )
+                    .prompt()
+                },
+            )?,
+        }
     } else {
         strategy
     };
 
-    let required_text = Text::new("Enter subtitle file name keyword (optional):").prompt()?;
-    let required_text = if required_text.is_empty() {
-        None
+    let required_text = match &cli.keyword {
+        Some(keyword) => vec![keyword.clone()],
+        None if cli.keyword_regex.is_some() => Vec::new(),
+        None => prompt_keywords(&input, &extensions, cli.include_hidden)?,
+    };
+    let required_text = required_text
+        .into_iter()
+        .filter(|k| !k.is_empty())
+        .map(|k| k.to_lowercase())
+        .collect();
+
+    let journal_dir = if output_dir {
+        output.as_path()
     } else {
-        Some(required_text.to_lowercase())
+        output.parent().unwrap_or(Path::new("."))
+    };
+    let progress_dir = cli.sub_out.as_deref().unwrap_or(journal_dir);
+    let _lock = match lock_output(&cli, journal_dir) {
+        Ok(lock) => lock,
+        Err(e) => {
+            eprintln!("{}", e.to_string().red().bold());
+            return Err(e.into());
+        }
+    };
+    let progress = match cli.resume.then(|| resume::Progress::load(progress_dir)) {
+        Some(Err(e)) => {
+            eprintln!("{}", e.to_string().red().bold());
+            return Err(e.into());
+        }
+        progress => progress.transpose()?,
+    };
+    if let Some(progress) = progress.as_ref().filter(|p| p.resumed() > 0) {
+        info!(
+            "Resuming, skipping {} media files finished earlier",
+            progress.resumed()
+        );
+    }
+    let options = SyncOptions {
+        strategy,
+        sort_strat,
+        required_text,
+        rules,
+        progress,
+        staging,
+        ..SyncOptions::new(&cli, link_mode, extensions, journal_dir)
     };
 
+    let mut shared: Vec<_> = seasons
+        .iter()
+        .flat_map(|season| {
+            season
+                .stems
+                .iter()
+                .filter(|(_, files)| files.len() > 1)
+                .map(|(stem, files)| {
+                    let names: Vec<_> = files.iter().map(|f| f.to_string_lossy()).collect();
+                    format!("{}: {}", season.qualify(stem), names.join(", "))
+                })
+        })
+        .collect();
+    shared.sort_unstable();
+
+    let mut results = Vec::new();
+    let mut limit = Limit::new(cli.limit);
+    let mut limited = Vec::new();
+
     match mode {
-        Mode::Season => {
-            // Match the subs folder to the media name
-            let mut entries: Vec<_> = read_dir(&cli.input)?.flatten().collect();
-            entries.sort_unstable_by_key(|e| e.file_name());
-            for sub_dir in entries {
-                let dir_name = sub_dir.file_name().to_string_lossy().to_string();
-                if let Some(media_file) = destination_stems.remove(&dir_name) {
-                    synchronize_folder(
-                        &sub_dir.path(),
-                        &media_file,
-                        strategy,
-                        sort_strat,
-                        cli.copy,
-                        cli.overwrite,
-                        &required_text,
-                    )?;
+        _ if cli.match_command.is_some() => {
+            let command = cli.match_command.as_deref().expect("checked to be given");
+            for season in &mut seasons {
+                let Some(input) = &season.input else {
+                    continue;
+                };
+                if let Some(name) = &season.name {
+                    info!("Synchronizing {}", name.bold());
+                }
+                let season_results =
+                    sync_command(command, input, &mut season.stems, &options, &mut limit)?;
+                results.extend(
+                    season_results
+                        .into_iter()
+                        .map(|(stem, result)| (season.qualify(&stem), result)),
+                );
+                limited.extend(limit.skipped.drain(..).map(|stem| season.qualify(&stem)));
+            }
+        }
+        Mode::Season | Mode::MultiSeason => {
+            for season in &mut seasons {
+                let Some(input) = &season.input else {
+                    continue;
+                };
+                if let Some(name) = &season.name {
+                    info!("Synchronizing {}", name.bold());
                 }
+                let season_results =
+                    match sync_season(input, &mut season.stems, &cli, &options, &mut limit) {
+                        Ok(season_results) => season_results,
+                        Err(e) if matches!(e.downcast_ref(), Some(SyncError::Declined)) => {
+                            println!("{}", e.to_string().yellow().bold());
+                            return Ok(ExitCode::SUCCESS);
+                        }
+                        // A season that can't be read fails on its own, like a folder would
+                        Err(e)
+                            if season.name.is_some()
+                                && e.downcast_ref::<SyncError>()
+                                    .is_some_and(|e| !e.is_prompt()) =>
+                        {
+                            eprintln!("{}", e.to_string().red().bold());
+                            let e = e
+                                .downcast::<SyncError>()
+                                .expect("checked to be a SyncError");
+                            results.push((season.name.clone().unwrap_or_default(), Err(*e)));
+                            continue;
+                        }
+                        Err(e) => return Err(e),
+                    };
+                results.extend(
+                    season_results
+                        .into_iter()
+                        .map(|(stem, result)| (season.qualify(&stem), result)),
+                );
+                limited.extend(limit.skipped.drain(..).map(|stem| season.qualify(&stem)));
             }
         }
+        Mode::Single if cli.stats => {
+            let stem = seasons[0].stems.keys().next().expect("one item exactly");
+            print_stats(
+                &[(stem.clone(), collect_candidates(&input, &options)?)],
+                &options,
+            );
+        }
+        Mode::Flat if cli.stats => {
+            print_stats(&flat_groups(&input, &seasons[0].stems, &options)?, &options);
+        }
         Mode::Single => {
+            let (stem, media_files) = seasons[0].stems.drain().next().expect("one item exactly");
+            match plan_folder(&input, &media_files, &options)
+                .and_then(|plan| execute_plan(&plan, &options))
             {
-                let media_file = destination_stems.iter().next().expect("one item exactly").1;
-                synchronize_folder(
-                    &cli.input,
-                    media_file,
-                    strategy,
-                    sort_strat,
-                    cli.copy,
-                    cli.overwrite,
-                    &required_text,
-                )?;
+                Err(e) if e.is_skip() => {
+                    seasons[0].stems.insert(stem, media_files);
+                }
+                result => {
+                    if let Err(e) = &result {
+                        eprintln!("{}", e.to_string().red().bold());
+                    }
+                    results.push((stem, result));
+                }
             }
-            destination_stems.clear();
         }
+        Mode::Flat => {
+            results = sync_flat(&input, &mut seasons[0].stems, &options, &mut limit)?;
+            limited = limit.skipped;
+        }
+        Mode::Manifest => {
+            let manifest = manifest.as_ref().expect("manifest mode has a manifest");
+            results = manifest.sync(&mut seasons[0].stems, &options, &mut limit)?;
+            limited = limit.skipped;
+        }
+        Mode::Pairs | Mode::Source => unreachable!("synchronized separately"),
     }
 
-    if destination_stems.is_empty() {
-        println!("{}", "Done!".green().bold());
-    } else {
-        println!(
-            "{}",
-            format!(
-                "Completed with {} matches, but didn't match:",
-                num_stems - destination_stems.len()
-            )
-            .yellow()
-            .bold()
-        );
-        for stem in destination_stems.keys() {
-            println!(" - {}", stem);
+    if cli.stats {
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    if let Some(progress) = options.progress.as_ref().filter(|_| !cli.dry_run) {
+        progress.finish()?;
+    }
+
+    results.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+
+    if cli.prune {
+        match mode {
+            Mode::Single => warn!(
+                "{}",
+                "Nothing to prune, --prune needs an output directory".yellow()
+            ),
+            Mode::Manifest => match manifest.as_ref().and_then(|m| m.media_dir.as_ref()) {
+                Some(dir) => prune(std::slice::from_ref(dir), &media_extensions, walk, &options)?,
+                None => warn!(
+                    "{}",
+                    "Nothing to prune, --prune needs a media-dir in the manifest".yellow()
+                ),
+            },
+            Mode::MultiSeason => {
+                let dirs: Vec<_> = seasons
+                    .iter()
+                    .filter_map(|season| season.name.as_ref().map(|name| output.join(name)))
+                    .collect();
+                prune(&dirs, &media_extensions, walk, &options)?;
+            }
+            _ => prune(
+                std::slice::from_ref(&output),
+                &media_extensions,
+                walk,
+                &options,
+            )?,
+        }
+    }
+
+    let specials: Vec<_> = seasons
+        .iter()
+        .filter_map(|season| season.name.clone())
+        .filter(|name| matching::is_specials(name))
+        .collect();
+    let unmatched: Vec<_> = seasons
+        .into_iter()
+        .filter(|season| !season.stems.is_empty())
+        .map(|season| {
+            let mut stems: Vec<_> = season.stems.into_keys().collect();
+            stems.sort_unstable();
+            (season.name, stems)
+        })
+        .collect();
+
+    if let Some(report_path) = &cli.report {
+        let unmatched_stems = unmatched
+            .iter()
+            .flat_map(|(season, stems)| {
+                stems.iter().map(move |stem| match season {
+                    Some(name) => format!("{name}/{stem}"),
+                    None => stem.clone(),
+                })
+            })
+            .collect();
+        report::Report::new(mode, &results, unmatched_stems, !cli.dry_run).write(report_path)?;
+    }
+
+    if let (Mode::Single, Some((_, Err(_)))) = (mode, results.last()) {
+        let Some((_, Err(e))) = results.pop() else {
+            unreachable!("last result is an error")
+        };
+        return Err(e.into());
+    }
+
+    print_summary(&results, &unmatched, &limited, &shared, cli.dry_run);
+    print_specials(&specials, &results, &unmatched);
+    Ok(exit_code(&results, !unmatched.is_empty()))
+}
+
+/// Prints the matches of each specials season on their own, since their folders and episode
+/// numbers rarely follow the other seasons
+fn print_specials(
+    specials: &[String],
+    results: &[FolderResult],
+    unmatched: &[(Option<String>, Vec<String>)],
+) {
+    if !output::enabled(output::Level::Quiet) {
+        return;
+    }
+    for name in specials {
+        let prefix = format!("{name}/");
+        let (matched, failed): (Vec<_>, Vec<_>) = results
+            .iter()
+            .filter(|(stem, _)| stem.starts_with(&prefix))
+            .partition(|(_, result)| result.is_ok());
+        let missing = unmatched
+            .iter()
+            .find(|(season, _)| season.as_deref() == Some(name.as_str()))
+            .map_or(0, |(_, stems)| stems.len());
+        println!(
+            "{} {}: {} matched, {} failed, {} unmatched",
+            "Specials".cyan().bold(),
+            name.bold(),
+            matched.len(),
+            failed.len(),
+            missing
+        );
+    }
+}
+
+/// Lists the orphaned subtitles in the output `dirs` and removes them once confirmed
+fn prune(
+    dirs: &[PathBuf],
+    media_extensions: &[String],
+    walk: destination::Walk,
+    options: &SyncOptions,
+) -> Result<(), SyncError> {
+    let mut subtitle_extensions = options.extensions.clone();
+    subtitle_extensions.extend(options.out_ext.clone());
+    let mut orphans = Vec::new();
+    for dir in dirs {
+        orphans.extend(destination::orphans(
+            dir,
+            media_extensions,
+            &subtitle_extensions,
+            walk,
+        )?);
+    }
+    if orphans.is_empty() {
+        verbose!("No orphaned subtitles to prune");
+        return Ok(());
+    }
+
+    info!("{}", "Orphaned subtitles:".yellow().bold());
+    for orphan in &orphans {
+        info!(" - {}", orphan.to_string_lossy());
+    }
+    if options.dry_run {
+        info!("Would remove {} orphaned subtitles", orphans.len());
+        return Ok(());
+    }
+
+    let confirmed = if options.yes {
+        true
+    } else if options.non_interactive {
+        info!(
+            "{}",
+            "Not removing orphaned subtitles without --yes".yellow()
+        );
+        false
+    } else {
+        output::prompt(
+            &format!("Remove {} orphaned subtitles?", orphans.len()),
+            false,
+            |message| Confirm::new(message).with_default(false).prompt(),
+        )?
+    };
+    if !confirmed {
+        return Ok(());
+    }
+
+    for orphan in orphans {
+        remove_file(&orphan).map_err(SyncError::io(&orphan))?;
+        if let Some(journal) = &options.journal {
+            journal.record(&journal::Entry::Removed {
+                target: orphan.clone(),
+                backup: None,
+            })?;
+        }
+        info!("Removed {}", orphan.to_string_lossy());
+    }
+    Ok(())
+}
+
+/// Succeeds when every media file was given a subtitle, otherwise exits with [`PARTIAL`]
+fn exit_code(results: &[FolderResult], unmatched: bool) -> ExitCode {
+    if unmatched || results.iter().any(|(_, result)| result.is_err()) {
+        ExitCode::from(PARTIAL)
+    } else {
+        ExitCode::SUCCESS
+    }
+}
+
+/// Locks the directory a run changes, where its journal is kept in `journal_dir` or --sub-out,
+/// unless nothing will be changed
+fn lock_output(cli: &SyncArgs, journal_dir: &Path) -> Result<Option<lock::RunLock>, SyncError> {
+    if cli.dry_run || cli.stats {
+        return Ok(None);
+    }
+    let dir = match &cli.sub_out {
+        Some(sub_out) => {
+            create_dir_all(sub_out).map_err(SyncError::io(sub_out))?;
+            sub_out
+        }
+        None => journal_dir,
+    };
+    lock::RunLock::acquire(dir, cli.wait, cli.force_unlock).map(Some)
+}
+
+/// Prints the failures and unmatched media of a run, grouped by season, the stems left over by
+/// --limit, and the total size of the subtitles placed
+fn print_summary(
+    results: &[FolderResult],
+    unmatched: &[(Option<String>, Vec<String>)],
+    limited: &[String],
+    shared: &[String],
+    dry_run: bool,
+) {
+    // --quiet-summary-only leaves just the line saying whether the run completed
+    let details = output::enabled(output::Level::Quiet);
+    if details && !shared.is_empty() {
+        println!("{}", "Shared by several media files:".cyan().bold());
+        for line in shared {
+            println!(" - {line}");
+        }
+    }
+
+    if details && !limited.is_empty() {
+        println!("{}", "Not processed due to --limit:".cyan().bold());
+        for stem in limited {
+            println!(" - {stem}");
+        }
+    }
+
+    let statuses: Vec<_> = results
+        .iter()
+        .filter_map(|(stem, result)| result.as_ref().ok().map(|o| (stem, o)))
+        .flat_map(|(stem, outcomes)| outcomes.iter().map(move |o| (stem, o)))
+        .map(|(stem, outcome)| {
+            let (mark, action) = outcome.status_action(dry_run);
+            let detail = format!(
+                "{} ({}){}",
+                outcome
+                    .source
+                    .file_name()
+                    .unwrap_or_default()
+                    .to_string_lossy(),
+                humansize::format_size(outcome.size, DECIMAL),
+                outcome
+                    .selected_by
+                    .as_ref()
+                    .map(|s| format!(" by {s}"))
+                    .unwrap_or_default()
+            );
+            (mark, stem, action, detail)
+        })
+        .collect();
+    let widths = statuses
+        .iter()
+        .fold((0, 0), |(name, action), (_, stem, done, _)| {
+            (
+                name.max(stem.chars().count()),
+                action.max(done.chars().count()),
+            )
+        });
+    for (mark, stem, action, detail) in &statuses {
+        info!(
+            "{}",
+            output::status_line(*mark, stem, action, detail, widths)
+        );
+    }
+
+    let failures: Vec<_> = results
+        .iter()
+        .filter_map(|(stem, result)| result.as_ref().err().map(|e| (stem, e)))
+        .collect();
+
+    if unmatched.is_empty() && failures.is_empty() {
+        println!("{}", "Done!".green().bold());
+    } else {
+        println!(
+            "{}",
+            format!("Completed with {} matches", results.len() - failures.len())
+                .yellow()
+                .bold()
+        );
+        if details && !unmatched.is_empty() {
+            println!("{}", "Didn't match:".yellow().bold());
+            for (season, stems) in unmatched {
+                let indent = match season {
+                    Some(name) => {
+                        println!(" {}:", name.bold());
+                        "   "
+                    }
+                    None => " ",
+                };
+                for stem in stems {
+                    println!("{indent}- {}", stem);
+                }
+            }
+        }
+        if details && !failures.is_empty() {
+            println!("{}", "Failed:".red().bold());
+            for (name, e) in &failures {
+                println!(" - {}: {}", name, e);
+            }
+        }
+    }
+    if !details {
+        return;
+    }
+
+    let mut placed: Vec<_> = results
+        .iter()
+        .filter_map(|(_, result)| result.as_ref().ok())
+        .flatten()
+        .collect();
+    if !placed.is_empty() {
+        let count = |status: Status| placed.iter().filter(|o| o.status == status).count();
+        println!(
+            "{} created, {} unchanged, {} replaced, {} skipped",
+            count(Status::Created),
+            count(Status::Unchanged),
+            count(Status::Replaced),
+            count(Status::Skipped)
+        );
+        let fell_back = placed.iter().filter(|o| o.fell_back).count();
+        if fell_back > 0 {
+            println!(
+                "{}",
+                format!("{fell_back} copied because symlinks couldn't be created").yellow()
+            );
+        }
+        let deduplicated: Vec<_> = placed.iter().filter(|o| o.deduplicated).collect();
+        if !deduplicated.is_empty() {
+            let bytes: u64 = deduplicated.iter().map(|outcome| outcome.size).sum();
+            println!(
+                "{} saved by hardlinking {} duplicate {}",
+                humansize::format_size(bytes, DECIMAL),
+                deduplicated.len(),
+                if deduplicated.len() == 1 {
+                    "copy"
+                } else {
+                    "copies"
+                }
+            );
+        }
+    }
+    placed.retain(|outcome| matches!(outcome.status, Status::Created | Status::Replaced));
+    if !placed.is_empty() {
+        let (copied, referenced): (Vec<&SyncOutcome>, Vec<_>) =
+            placed.iter().partition(|outcome| {
+                matches!(outcome.operation, "copy" | "shift" | "merge" | "convert")
+            });
+        let mut totals = Vec::new();
+        for (outcomes, label) in [
+            (copied, if dry_run { "to copy" } else { "copied" }),
+            (referenced, "referenced"),
+        ] {
+            if !outcomes.is_empty() {
+                let bytes: u64 = outcomes.iter().map(|outcome| outcome.size).sum();
+                totals.push(format!(
+                    "{} {label}",
+                    humansize::format_size(bytes, DECIMAL)
+                ));
+            }
+        }
+        println!(
+            "Total: {} {}, {}",
+            placed.len(),
+            if placed.len() == 1 { "file" } else { "files" },
+            totals.join(", ")
+        );
+    }
+}
+
+/// Places each subtitle listed in `pairs`, a file or `-` for stdin, next to the media it's paired
+/// with. Malformed lines are reported and skipped
+fn sync_pairs(
+    pairs: &Path,
+    cli: &SyncArgs,
+    link_mode: LinkMode,
+    extensions: Vec<String>,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let content = if pairs == Path::new("-") {
+        io::read_to_string(io::stdin())?
+    } else {
+        read_to_string(pairs).map_err(SyncError::io(pairs))?
+    };
+
+    let _lock = match lock_output(cli, Path::new(".")) {
+        Ok(lock) => lock,
+        Err(e) => {
+            eprintln!("{}", e.to_string().red().bold());
+            return Err(e.into());
+        }
+    };
+    let mut options = SyncOptions::new(cli, link_mode, extensions, Path::new("."));
+    // Stdin holds the pairs, so confirmations can't be answered
+    options.non_interactive = true;
+
+    let mut used_targets = HashSet::new();
+    let mut results = Vec::new();
+    for (n, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let Some((source, media)) = line
+            .split_once('\t')
+            .filter(|(source, media)| !source.is_empty() && !media.is_empty())
+        else {
+            warn!(
+                "{}",
+                format!(
+                    "Skipping line {}: expected `subtitle<TAB>media`, found `{line}`",
+                    n + 1
+                )
+                .yellow()
+            );
+            continue;
+        };
+
+        let (source, media) = (PathBuf::from(source), PathBuf::from(media));
+        let result = pair_candidate(&source).and_then(|candidate| {
+            let lang = options
+                .lang
+                .clone()
+                .or_else(|| lang::detect(&candidate.name));
+            let placement = Placement {
+                // The subtitle was named rather than selected
+                selected_by: None,
+                ..Placement::new(&candidate, &media, lang, &mut used_targets, &options)?
+            };
+            let plan = FolderPlan {
+                placements: vec![placement],
+            };
+            execute_plan(&plan, &options)
+        });
+        if let Err(e) = &result {
+            eprintln!("{}", e.to_string().red().bold());
+        }
+        results.push((media.to_string_lossy().to_string(), result));
+    }
+
+    if let Some(report_path) = &cli.report {
+        report::Report::new(Mode::Pairs, &results, Vec::new(), !cli.dry_run).write(report_path)?;
+    }
+    print_summary(&results, &[], &[], &[], cli.dry_run);
+    Ok(exit_code(&results, false))
+}
+
+/// Places `source` for every media file in `output`, a directory or a single media file, without
+/// selecting anything
+fn sync_source(
+    source: &Path,
+    output: &Path,
+    cli: &SyncArgs,
+    link_mode: LinkMode,
+    extensions: Vec<String>,
+    media_extensions: &[String],
+) -> Result<ExitCode, Box<dyn Error>> {
+    let candidate = match pair_candidate(source) {
+        Ok(candidate) => candidate,
+        Err(e) => {
+            eprintln!("{}", e.to_string().red().bold());
+            return Err(e.into());
+        }
+    };
+
+    let (stems, journal_dir) = if output.is_dir() {
+        let walk = destination::Walk {
+            recursive: cli.recursive,
+            follow_symlinks: cli.follow_symlinks,
+            include_hidden: cli.include_hidden,
+        };
+        (destination::scan(output, media_extensions, walk)?, output)
+    } else {
+        let stems = [(media_stem(output)?, vec![output.to_path_buf()])].into();
+        (stems, output.parent().unwrap_or(Path::new(".")))
+    };
+    if stems.is_empty() {
+        eprintln!("{}", "No destination files!".red().bold());
+        return Err("No files".into());
+    }
+    let mut stems: Vec<_> = stems.into_iter().collect();
+    stems.sort_unstable();
+
+    info!(
+        "Placing {} for {} {}",
+        candidate.name.bold(),
+        stems.len().to_string().bold(),
+        if stems.len() > 1 { "entries" } else { "entry" }
+    );
+    if stems.len() > 1 {
+        warn!(
+            "{}",
+            format!(
+                "The same subtitle will be placed for all {} media files",
+                stems.len()
+            )
+            .yellow()
+        );
+    }
+    if cli.dry_run {
+        info!("{}", "Dry run, no files will be changed".cyan());
+    }
+
+    let _lock = match lock_output(cli, journal_dir) {
+        Ok(lock) => lock,
+        Err(e) => {
+            eprintln!("{}", e.to_string().red().bold());
+            return Err(e.into());
+        }
+    };
+    let options = SyncOptions::new(cli, link_mode, extensions, journal_dir);
+    let lang = options
+        .lang
+        .clone()
+        .or_else(|| lang::detect(&candidate.name));
+    let mut results = Vec::new();
+    for (stem, media_files) in stems {
+        let mut parents = HashSet::new();
+        let result = media_files
+            .iter()
+            .filter(|file| parents.insert(file.parent()))
+            .map(|media| {
+                Ok(Placement {
+                    // The subtitle was named rather than selected
+                    selected_by: None,
+                    ..Placement::new(
+                        &candidate,
+                        media,
+                        lang.clone(),
+                        &mut HashSet::new(),
+                        &options,
+                    )?
+                })
+            })
+            .collect::<Result<_, SyncError>>()
+            .and_then(|placements| execute_plan(&FolderPlan { placements }, &options));
+        match result {
+            Err(e) if e.is_prompt() => return Err(e.into()),
+            result => {
+                if let Err(e) = &result {
+                    eprintln!("{}", e.to_string().red().bold());
+                }
+                results.push((stem, result));
+            }
+        }
+    }
+
+    if let Some(report_path) = &cli.report {
+        report::Report::new(Mode::Source, &results, Vec::new(), !cli.dry_run).write(report_path)?;
+    }
+    print_summary(&results, &[], &[], &[], cli.dry_run);
+    Ok(exit_code(&results, false))
+}
+
+/// Prints the stem, OpenSubtitles hash, size, and path of every media file at `path`, a
+/// directory or a single file, one tab-separated line each
+fn print_hashes(
+    path: &Path,
+    media_extensions: &[String],
+    walk: destination::Walk,
+) -> Result<ExitCode, Box<dyn Error>> {
+    let mut media: Vec<_> = if path.is_dir() {
+        destination::scan(path, media_extensions, walk)?
+            .into_iter()
+            .flat_map(|(stem, files)| files.into_iter().map(move |file| (stem.clone(), file)))
+            .collect()
+    } else {
+        vec![(media_stem(path)?, path.to_path_buf())]
+    };
+    if media.is_empty() {
+        eprintln!("{}", "No destination files!".red().bold());
+        return Err("No files".into());
+    }
+    media.sort_unstable();
+
+    let mut failed = false;
+    for (stem, file) in media {
+        match fs_ops::opensubtitles_hash(&file) {
+            Ok((hash, size)) => {
+                println!("{stem}\t{hash:016x}\t{size}\t{}", file.to_string_lossy())
+            }
+            Err(e) => {
+                eprintln!("{}", SyncError::io(&file)(e).to_string().red().bold());
+                failed = true;
+            }
+        }
+    }
+    Ok(if failed {
+        ExitCode::from(PARTIAL)
+    } else {
+        ExitCode::SUCCESS
+    })
+}
+
+/// Builds the candidate for a subtitle named explicitly in a pair
+fn pair_candidate(source: &Path) -> Result<Candidate, SyncError> {
+    let (Some(name), Some(_)) = (source.file_name(), source.extension()) else {
+        return Err(SyncError::InvalidPath(source.to_path_buf()));
+    };
+    let metadata = metadata(source).map_err(SyncError::io(source))?;
+    Ok(Candidate {
+        path: source.to_path_buf(),
+        name: name.to_string_lossy().to_string(),
+        listed: name.to_string_lossy().to_string(),
+        size: metadata.len(),
+        modified: metadata.modified().ok(),
+        cues: OnceLock::new(),
+    })
+}
+
+/// The subtitles the alphabetical, size, and duration strategies would select among
+/// `candidates`, the last two only when they have a size or duration to compare
+fn strategy_picks<'a>(
+    candidates: &'a [Candidate],
+    options: &SyncOptions,
+) -> [Option<&'a Candidate>; 3] {
+    let alphabetical = candidates
+        .iter()
+        .min_by(|a, b| options.compare_candidates(a, b));
+    let size = candidates.iter().max_by_key(|c| c.size);
+    let duration = candidates
+        .iter()
+        .filter(|c| has_extension(&c.path, &["srt".to_string()]))
+        .filter_map(|c| Some((srt::end_time(&c.path).ok()?, c)))
+        .max_by_key(|(end, _)| *end)
+        .map(|(_, c)| c);
+    [alphabetical, size, duration]
+}
+
+/// Prints a table of the subtitle each strategy would select for every stem in `rows`
+fn print_stats(rows: &[(String, Vec<Candidate>)], options: &SyncOptions) {
+    let header = ["Media", "Alphabetical", "Size", "Duration"].map(String::from);
+    let mut table = vec![header];
+    for (stem, candidates) in rows {
+        let [alphabetical, size, duration] = strategy_picks(candidates, options)
+            .map(|c| c.map_or("-".to_string(), |c| c.listed.clone()));
+        table.push([stem.clone(), alphabetical, size, duration]);
+    }
+
+    let mut widths = [0; 4];
+    for row in &table {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    for (i, row) in table.iter().enumerate() {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        let line = line.trim_end();
+        if i == 0 {
+            println!("{}", line.bold());
+        } else {
+            println!("{line}");
+        }
+    }
+}
+
+/// The stem of a media file and the outcome of synchronizing subtitles to it
+type FolderResult = (String, Result<Vec<SyncOutcome>, SyncError>);
+
+/// Pairs the subtitle folders of `input` with the media in `destination_stems`, removing the
+/// stems it matches, and synchronizes each pair. Only an aborted prompt ends the season early
+fn sync_season(
+    input: &Path,
+    destination_stems: &mut HashMap<String, Vec<PathBuf>>,
+    cli: &SyncArgs,
+    options: &SyncOptions,
+    limit: &mut Limit,
+) -> Result<Vec<FolderResult>, Box<dyn Error>> {
+    let match_by = cli.match_by.unwrap_or(MatchBy::Name);
+    // Match the subs folder to the media name
+    let mut entries: Vec<_> = read_dir(input)
+        .map_err(SyncError::io(input))?
+        .flatten()
+        .filter(|e| options.include_hidden || !destination::is_hidden(&e.path()))
+        .collect();
+    if options.natural_sort {
+        entries.sort_by(|a, b| {
+            matching::natural_cmp(
+                &a.file_name().to_string_lossy(),
+                &b.file_name().to_string_lossy(),
+            )
+        });
+    } else {
+        entries.sort_unstable_by_key(|e| e.file_name());
+    }
+    let media_count = destination_stems.len();
+    let mut stems: Vec<_> = destination_stems.keys().cloned().collect();
+    stems.sort_unstable_by(|a, b| options.compare_names(a, b));
+    let mut pairs = Vec::new();
+    let mut unmatched_dirs = Vec::new();
+    for entry in entries {
+        let path = entry.path();
+        // Archives stand in for the folder they'd be extracted to
+        let (dir_name, sub_dir) = if archive::is_archive(&path) {
+            match options.staging.extract(&path, &options.extensions) {
+                Ok(dir) => (
+                    path.file_stem()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .to_string(),
+                    dir,
+                ),
+                Err(e) => {
+                    eprintln!("{}", e.to_string().red().bold());
+                    continue;
+                }
+            }
+        } else {
+            (entry.file_name().to_string_lossy().to_string(), path)
+        };
+        let stem = matching::find_stem(&dir_name, destination_stems, options.match_rules);
+        if let Some((stem, media_files)) = stem
+            .cloned()
+            .and_then(|stem| destination_stems.remove_entry(&stem))
+        {
+            pairs.push(Pairing {
+                dir_name,
+                sub_dir,
+                stem,
+                media_files,
+                second_dir: None,
+                matched_by: MatchKind::Name,
+            });
+            continue;
+        }
+        unmatched_dirs.push((dir_name, sub_dir));
+    }
+
+    let unit_count = pairs.len() + unmatched_dirs.len();
+
+    if match_by == MatchBy::Episode {
+        unmatched_dirs = match_by_episode(unmatched_dirs, destination_stems, &mut pairs);
+    }
+
+    if cli.contains {
+        unmatched_dirs = match_by_token(unmatched_dirs, destination_stems, &mut pairs);
+    }
+
+    if let Some((threshold, metric, matched_by)) = fuzzy_matching(cli) {
+        let label = match matched_by {
+            MatchKind::Similarity => "Similarity",
+            _ => "Fuzzy",
+        };
+        for (dir_name, sub_dir) in std::mem::take(&mut unmatched_dirs) {
+            if matched_by == MatchKind::Similarity && output::enabled(output::Level::Verbose) {
+                let mut scores: Vec<_> = destination_stems
+                    .keys()
+                    .map(|stem| (matching::score(&dir_name, stem, metric), stem))
+                    .collect();
+                scores.sort_unstable_by(|a, b| b.0.total_cmp(&a.0).then_with(|| a.1.cmp(b.1)));
+                verbose!("Closest titles to {}:", dir_name.bold());
+                for (score, stem) in scores.iter().take(3) {
+                    verbose!(" - {score:.3} {stem}");
+                }
+            }
+            let best =
+                matching::best_matches(&dir_name, destination_stems.keys(), threshold, metric);
+            let stem = match best.as_slice() {
+                [] => {
+                    unmatched_dirs.push((dir_name, sub_dir));
+                    continue;
+                }
+                [stem] => (*stem).clone(),
+                tied if cli.non_interactive => {
+                    info!(
+                        "{}",
+                        format!(
+                            "Ambiguous match for {}: {}",
+                            dir_name,
+                            tied.iter()
+                                .map(|s| s.as_str())
+                                .collect::<Vec<_>>()
+                                .join(", ")
+                        )
+                        .yellow()
+                    );
+                    unmatched_dirs.push((dir_name, sub_dir));
+                    continue;
+                }
+                tied => {
+                    let choice = output::prompt(
+                        &format!("Multiple media files match {}:", dir_name.bold()),
+                        None,
+                        |message| {
+                            Select::new(message, tied.iter().map(|s| s.to_string()).collect())
+                                .prompt()
+                                .map(Some)
+                        },
+                    )?;
+                    // Left unmatched when the prompt went unanswered
+                    let Some(stem) = choice else {
+                        unmatched_dirs.push((dir_name, sub_dir));
+                        continue;
+                    };
+                    stem
+                }
+            };
+            info!("{label} matched {} to {}", dir_name.bold(), stem.bold());
+            let media_files = destination_stems.remove(&stem).expect("stem to exist");
+            pairs.push(Pairing {
+                dir_name,
+                sub_dir,
+                stem,
+                media_files,
+                second_dir: None,
+                matched_by,
+            });
+        }
+    }
+    pairs.sort_unstable_by(|a, b| a.dir_name.cmp(&b.dir_name));
+
+    if cli.explain {
+        explain_matching(
+            &stems,
+            &pairs,
+            &unmatched_dirs,
+            fuzzy_matching(cli).map(|(_, metric, _)| metric),
+            options,
+        );
+    }
+
+    if unit_count != media_count {
+        warn_count_mismatch(unit_count, media_count, &unmatched_dirs, destination_stems);
+    }
+
+    // Media finished by an earlier run is neither synchronized again nor reported unmatched
+    pairs.retain(|pairing| !options.resumes(&pairing.stem, &pairing.media_files));
+
+    if cli.review && !pairs.is_empty() {
+        pairs = review_pairs(pairs, destination_stems)?;
+    }
+
+    if cli.stats {
+        let mut rows = Vec::new();
+        for pairing in pairs {
+            match collect_candidates(&pairing.sub_dir, options) {
+                Ok(candidates) => rows.push((pairing.stem, candidates)),
+                Err(e) => eprintln!("{}", e.to_string().red().bold()),
+            }
+        }
+        print_stats(&rows, options);
+        return Ok(Vec::new());
+    }
+
+    if cli.confirm {
+        return confirm_season(pairs, destination_stems, options, limit);
+    }
+
+    let progress = output::Progress::start(pairs.len());
+    let run = |pairing: &Pairing| {
+        let result = plan_pairing(pairing, options)
+            .and_then(|plan| execute_tracked(&plan, &pairing.media_files, options));
+        if let Err(e) = &result {
+            if !e.is_prompt() && !e.is_skip() {
+                output::suspend(|| eprintln!("{}", e.to_string().red().bold()));
+            }
+        }
+        progress.inc();
+        result
+    };
+
+    // Prompts must happen one at a time, so only non-interactive runs are parallel. A limit is
+    // only exact when folders are synchronized in order
+    let mut results = Vec::new();
+    if cli.non_interactive && limit.remaining.is_none() {
+        let mut pool = rayon::ThreadPoolBuilder::new();
+        if let Some(jobs) = cli.jobs {
+            pool = pool.num_threads(jobs.into());
+        }
+        results = pool.build()?.install(|| {
+            pairs
+                .into_par_iter()
+                .map(|pairing| {
+                    let result = run(&pairing);
+                    (pairing.stem, result)
+                })
+                .collect()
+        });
+    } else {
+        for pairing in pairs {
+            if limit.stopped {
+                destination_stems.insert(pairing.stem, pairing.media_files);
+                continue;
+            }
+            if !limit.allows(&pairing.stem) {
+                continue;
+            }
+            let result = run(&pairing);
+            match result {
+                Err(e) if e.is_prompt() => return Err(e.into()),
+                Err(e) if e.is_skip() => {
+                    limit.stopped = matches!(e, SyncError::SkippedRest);
+                    destination_stems.insert(pairing.stem, pairing.media_files);
+                }
+                result => {
+                    limit.record(&result);
+                    results.push((pairing.stem, result));
+                }
+            }
+        }
+    }
+    Ok(results)
+}
+
+/// Lets the user uncheck wrong pairings, returning their media to `destination_stems`. Every
+/// pairing is kept if the prompt goes unanswered
+fn review_pairs(
+    pairs: Vec<Pairing>,
+    destination_stems: &mut HashMap<String, Vec<PathBuf>>,
+) -> Result<Vec<Pairing>, InquireError> {
+    let choices: Vec<_> = pairs
+        .iter()
+        .map(|p| format!("{} -> {}", p.dir_name, p.stem))
+        .collect();
+    let kept = output::prompt(
+        "Uncheck any wrong matches:",
+        (0..pairs.len()).collect(),
+        |message| {
+            MultiSelect::new(message, choices)
+                .with_all_selected_by_default()
+                .raw_prompt()
+                .map(|chosen| chosen.into_iter().map(|c| c.index).collect::<Vec<_>>())
+        },
+    )?;
+
+    let mut reviewed = Vec::new();
+    for (i, pairing) in pairs.into_iter().enumerate() {
+        if kept.contains(&i) {
+            reviewed.push(pairing);
+        } else {
+            destination_stems.insert(pairing.stem, pairing.media_files);
+        }
+    }
+    Ok(reviewed)
+}
+
+/// Warns that a season has `units` subtitle folders but `media` media files, listing the folders
+/// and media left without a counterpart. Matched pairs are still synchronized
+fn warn_count_mismatch(
+    units: usize,
+    media: usize,
+    unmatched_dirs: &[(String, PathBuf)],
+    destination_stems: &HashMap<String, Vec<PathBuf>>,
+) {
+    warn!(
+        "{}",
+        format!("Found {units} subtitle folders for {media} media files")
+            .yellow()
+            .bold()
+    );
+    let mut stems: Vec<_> = destination_stems.keys().collect();
+    stems.sort_unstable();
+    for stem in stems {
+        warn!(" - {} {}", "No subtitles for".yellow(), stem);
+    }
+    for (dir_name, _) in unmatched_dirs {
+        warn!(" - {} {}", "No media for".yellow(), dir_name);
+    }
+}
+
+/// Selects the subtitles of every pairing, prints where they'd be placed, and only places them
+/// once the user agrees. Declining ends the run without changing anything
+fn confirm_season(
+    pairs: Vec<Pairing>,
+    destination_stems: &mut HashMap<String, Vec<PathBuf>>,
+    options: &SyncOptions,
+    limit: &mut Limit,
+) -> Result<Vec<FolderResult>, Box<dyn Error>> {
+    let mut plans = Vec::new();
+    for pairing in pairs {
+        if limit.stopped {
+            destination_stems.insert(pairing.stem, pairing.media_files);
+            continue;
+        }
+        if !limit.allows(&pairing.stem) {
+            continue;
+        }
+        let plan = plan_pairing(&pairing, options);
+        match plan {
+            Err(e) if e.is_prompt() => return Err(e.into()),
+            Err(e) if e.is_skip() => {
+                limit.stopped = matches!(e, SyncError::SkippedRest);
+                destination_stems.insert(pairing.stem, pairing.media_files);
+            }
+            plan => {
+                limit.record(&plan);
+                plans.push((pairing.stem, pairing.media_files, plan));
+            }
+        }
+    }
+
+    println!("{}", "Planned:".cyan().bold());
+    for (stem, _, plan) in &plans {
+        match plan {
+            Ok(plan) => {
+                for placement in &plan.placements {
+                    let appended = placement
+                        .appended
+                        .as_ref()
+                        .map(|a| format!(" + {}", a.path.to_string_lossy()))
+                        .unwrap_or_default();
+                    println!(
+                        " - {stem}: {}{appended} -> {}",
+                        placement.source.path.to_string_lossy(),
+                        placement.target.to_string_lossy()
+                    );
+                }
+            }
+            Err(e) => println!(" - {stem}: {}", e.to_string().red()),
+        }
+    }
+    if !output::prompt("Proceed?", false, |message| {
+        Confirm::new(message).with_default(false).prompt()
+    })? {
+        return Err(SyncError::Declined.into());
+    }
+
+    let progress = output::Progress::start(plans.len());
+    let mut results = Vec::new();
+    for (stem, media_files, plan) in plans {
+        let result = plan.and_then(|plan| execute_tracked(&plan, &media_files, options));
+        if let Err(e) = &result {
+            if !e.is_prompt() {
+                output::suspend(|| eprintln!("{}", e.to_string().red().bold()));
+            }
+        }
+        progress.inc();
+        results.push((stem, result));
+    }
+    Ok(results)
+}
+
+/// Groups the subtitle files directly inside `input` by the media stem they start with, or
+/// failing that the stem sharing their episode number, sorted by stem
+fn flat_groups(
+    input: &Path,
+    destination_stems: &HashMap<String, Vec<PathBuf>>,
+    options: &SyncOptions,
+) -> Result<Vec<(String, Vec<Candidate>)>, SyncError> {
+    let mut groups: HashMap<String, Vec<Candidate>> = HashMap::new();
+    for candidate in collect_candidates(input, options)? {
+        match flat_stem(
+            &candidate.name,
+            destination_stems.keys(),
+            options.match_rules,
+        ) {
+            Some(stem) => groups.entry(stem).or_default().push(candidate),
+            None => verbose!("No media matches {}", candidate.path.to_string_lossy()),
+        }
+    }
+    let mut groups: Vec<_> = groups.into_iter().collect();
+    groups.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+    Ok(groups)
+}
+
+/// Groups the subtitle files directly inside `input` by the media stem they start with, or
+/// failing that the stem sharing their episode number, and synchronizes each group as if it
+/// were a folder. Matched stems are removed from `destination_stems`
+fn sync_flat(
+    input: &Path,
+    destination_stems: &mut HashMap<String, Vec<PathBuf>>,
+    options: &SyncOptions,
+    limit: &mut Limit,
+) -> Result<Vec<FolderResult>, SyncError> {
+    let groups = flat_groups(input, destination_stems, options)?;
+    let mut units = Units::new(groups.len(), destination_stems, options, limit);
+    for (stem, candidates) in groups {
+        let Some(media_files) = units.take(&stem) else {
+            continue;
+        };
+        verbose!("Considering for {stem}:");
+        for candidate in &candidates {
+            verbose!(" - {candidate}");
+        }
+        let plan = plan_candidates(
+            input,
+            candidates,
+            &media_files,
+            options.lang.clone(),
+            options,
+        );
+        units.run(stem, media_files, plan)?;
+    }
+    Ok(units.finish())
+}
+
+/// Asks --match-command which subtitle under `input` each media stem in `destination_stems` gets
+/// and synchronizes those. The stems it answers for are removed from `destination_stems`
+fn sync_command(
+    command: &str,
+    input: &Path,
+    destination_stems: &mut HashMap<String, Vec<PathBuf>>,
+    options: &SyncOptions,
+    limit: &mut Limit,
+) -> Result<Vec<FolderResult>, SyncError> {
+    let mut stems: Vec<_> = destination_stems.keys().cloned().collect();
+    stems.sort_unstable_by(|a, b| options.compare_names(a, b));
+    let mut units = Units::new(stems.len(), destination_stems, options, limit);
+    for stem in stems {
+        let Some(media_files) = units.take(&stem) else {
+            continue;
+        };
+        let chosen = match external::choose(command, input, &stem, &media_files) {
+            Ok(Some(subtitle)) => Ok(subtitle),
+            Ok(None) => {
+                verbose!("No subtitle chosen for {stem}");
+                units.leave(stem, media_files);
+                continue;
+            }
+            Err(e) => Err(e),
+        };
+        let plan = chosen.and_then(|subtitle| {
+            let dir = subtitle.parent().unwrap_or(input).to_path_buf();
+            let candidate = pair_candidate(&subtitle)?;
+            plan_candidates(
+                &dir,
+                vec![candidate],
+                &media_files,
+                options.lang.clone(),
+                options,
+            )
+        });
+        units.run(stem, media_files, plan)?;
+    }
+    Ok(units.finish())
+}
+
+/// Stem of a media file named on its own, such as the output in single mode or in a manifest
+fn media_stem(media: &Path) -> Result<String, SyncError> {
+    destination::stem(media).ok_or_else(|| SyncError::InvalidPath(media.to_path_buf()))
+}
+
+/// Finds the media stem a flat subtitle file belongs to: the longest stem its name starts with,
+/// such as `Show.S01E01` for `Show.S01E01.eng.srt`, or else the only stem with its episode number,
+/// including stems covering several episodes such as `Show.S01E01E02`
+fn flat_stem<'a>(
+    name: &str,
+    stems: impl Iterator<Item = &'a String> + Clone,
+    rules: matching::MatchRules,
+) -> Option<String> {
+    let key = rules.key(name);
+    let prefixed = stems
+        .clone()
+        .filter(|stem| rules.key_starts_with(&key, &rules.key(stem)))
+        .max_by_key(|stem| stem.len());
+    if let Some(stem) = prefixed {
+        return Some(stem.clone());
+    }
+
+    let episode = matching::extract_episode(name.rsplit_once('.').map_or(name, |(stem, _)| stem))?;
+    let mut same = stems.filter(|stem| matching::extract_episodes(stem).contains(&episode));
+    match (same.next(), same.next()) {
+        (Some(stem), None) => Some(stem.clone()),
+        _ => None,
+    }
+}
+
+/// Describes the episodes found in a name for --explain
+fn describe_episodes(episodes: &[(u32, u32)]) -> String {
+    if episodes.is_empty() {
+        return "no episode".to_string();
+    }
+    let episodes: Vec<_> = episodes
+        .iter()
+        .map(|(season, episode)| format!("S{season:02}E{episode:02}"))
+        .collect();
+    format!("episode {}", episodes.join(", "))
+}
+
+/// The threshold and metric folders without an exact match are paired by, from --fuzzy or
+/// --similarity, along with which of the two it was
+fn fuzzy_matching(cli: &SyncArgs) -> Option<(f64, Metric, MatchKind)> {
+    match (cli.fuzzy, cli.similarity) {
+        (Some(threshold), _) => Some((threshold, Metric::Bigram, MatchKind::Fuzzy)),
+        (None, Some(threshold)) => Some((
+            threshold,
+            cli.metric.unwrap_or_default(),
+            MatchKind::Similarity,
+        )),
+        (None, None) => None,
+    }
+}
+
+/// Prints for --explain how the media `stems` and subtitle folders of a season were compared:
+/// the key each name is compared by under the match rules, the episodes found in it, and what
+/// each folder was paired with. Unpaired folders name their closest stem with --fuzzy or
+/// --similarity
+fn explain_matching(
+    stems: &[String],
+    pairs: &[Pairing],
+    unmatched_dirs: &[(String, PathBuf)],
+    metric: Option<Metric>,
+    options: &SyncOptions,
+) {
+    let rules = options.match_rules;
+    info!("Media:");
+    for stem in stems {
+        info!(
+            " - {stem}: key `{}`, {}",
+            rules.key(stem),
+            describe_episodes(&matching::extract_episodes(stem))
+        );
+    }
+
+    let mut folders: Vec<_> = pairs
+        .iter()
+        .flat_map(|pairing| {
+            let matched = format!("matched {} by {}", pairing.stem.bold(), pairing.matched_by);
+            let second = pairing.second_dir.as_ref().map(|dir| {
+                let name = dir.file_name().unwrap_or_default().to_string_lossy();
+                let described = format!("second episode of {}", pairing.stem.bold());
+                (name.to_string(), described.green())
+            });
+            [Some((pairing.dir_name.clone(), matched.green()))]
+                .into_iter()
+                .chain([second])
+                .flatten()
+        })
+        .collect();
+    for (dir_name, _) in unmatched_dirs {
+        let closest = metric.and_then(|metric| {
+            stems
+                .iter()
+                .map(|stem| (matching::score(dir_name, stem, metric), stem))
+                .max_by(|a, b| a.0.total_cmp(&b.0))
+        });
+        let described = match closest {
+            Some((score, stem)) => format!("unmatched, closest {stem} at {score:.2}"),
+            None => "unmatched".to_string(),
+        };
+        folders.push((dir_name.clone(), described.yellow()));
+    }
+    folders.sort_unstable_by(|a, b| options.compare_names(&a.0, &b.0));
+
+    info!("Subtitle folders:");
+    for (dir_name, described) in folders {
+        let episode = match matching::extract_episode(&dir_name) {
+            Some(episode) => describe_episodes(&[episode]),
+            None => describe_episodes(&[]),
+        };
+        info!(
+            " - {dir_name}: key `{}`, {episode}: {described}",
+            rules.key(&dir_name)
+        );
+    }
+}
+
+/// A subtitle folder paired with the media file it provides subtitles for
+#[derive(Debug)]
+struct Pairing {
+    dir_name: String,
+    sub_dir: PathBuf,
+    stem: String,
+    /// Every media file sharing the stem, such as both an `.mkv` and an `.mp4`
+    media_files: Vec<PathBuf>,
+    /// Folder for the second episode of a media file covering two, such as `Show.S01E01E02`
+    second_dir: Option<PathBuf>,
+    matched_by: MatchKind,
+}
+
+/// How a subtitle folder was paired with its media file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MatchKind {
+    Name,
+    Episode,
+    Token,
+    /// Bigram similarity under --fuzzy
+    Fuzzy,
+    /// Title distance under --similarity
+    Similarity,
+}
+
+impl Display for MatchKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Name => "name",
+            Self::Episode => "episode",
+            Self::Token => "token",
+            Self::Fuzzy => "fuzzy",
+            Self::Similarity => "similarity",
+        })
+    }
+}
+
+/// How many more folders may be synchronized, under --limit or until the user skips the rest,
+/// and the stems --limit left out
+#[derive(Debug)]
+struct Limit {
+    /// Successful synchronizations left, or `None` without a limit
+    remaining: Option<u64>,
+    skipped: Vec<String>,
+    /// The user chose to skip every remaining folder, leaving them unmatched
+    stopped: bool,
+}
+
+impl Limit {
+    fn new(limit: Option<u64>) -> Self {
+        Self {
+            remaining: limit,
+            skipped: Vec::new(),
+            stopped: false,
+        }
+    }
+
+    /// Whether the folder for `stem` may be synchronized, recording it as skipped if not
+    fn allows(&mut self, stem: &str) -> bool {
+        if self.remaining == Some(0) {
+            self.skipped.push(stem.to_string());
+            false
+        } else {
+            true
+        }
+    }
+
+    /// Counts a synchronization against the limit if it succeeded
+    fn record<T, E>(&mut self, result: &Result<T, E>) {
+        if let (Some(remaining), Ok(_)) = (&mut self.remaining, result) {
+            *remaining -= 1;
+        }
+    }
+}
+
+/// Media synchronized one stem at a time, as in flat mode, with --match-command, or from a
+/// manifest, along with the results so far
+struct Units<'a> {
+    destination_stems: &'a mut HashMap<String, Vec<PathBuf>>,
+    options: &'a SyncOptions,
+    limit: &'a mut Limit,
+    progress: output::Progress,
+    results: Vec<FolderResult>,
+}
+
+impl<'a> Units<'a> {
+    /// Starts synchronizing `total` units of the media in `destination_stems`
+    fn new(
+        total: usize,
+        destination_stems: &'a mut HashMap<String, Vec<PathBuf>>,
+        options: &'a SyncOptions,
+        limit: &'a mut Limit,
+    ) -> Self {
+        Self {
+            destination_stems,
+            options,
+            limit,
+            progress: output::Progress::start(total),
+            results: Vec::new(),
+        }
+    }
+
+    /// Takes the media sharing `stem` out of the destination to synchronize it, unless the run
+    /// stopped, an earlier unit took it already, an earlier run with --resume finished it, or
+    /// --limit was reached
+    fn take(&mut self, stem: &str) -> Option<Vec<PathBuf>> {
+        if self.limit.stopped {
+            return None;
+        }
+        let media_files = self.destination_stems.remove(stem)?;
+        if self.options.resumes(stem, &media_files) || !self.limit.allows(stem) {
+            return None;
+        }
+        Some(media_files)
+    }
+
+    /// Puts the media sharing `stem` back in the destination, where it's reported as unmatched
+    fn leave(&mut self, stem: String, media_files: Vec<PathBuf>) {
+        self.destination_stems.insert(stem, media_files);
+        self.progress.inc();
+    }
+
+    /// Executes `plan` for the media sharing `stem` and records the result. Media the user
+    /// skipped is left unmatched, and only an aborted prompt is returned as an error
+    fn run(
+        &mut self,
+        stem: String,
+        media_files: Vec<PathBuf>,
+        plan: Result<FolderPlan, SyncError>,
+    ) -> Result<(), SyncError> {
+        let result = plan.and_then(|plan| execute_tracked(&plan, &media_files, self.options));
+        match &result {
+            Err(e) if e.is_prompt() => return Err(result.unwrap_err()),
+            Err(e) if e.is_skip() => {
+                self.limit.stopped = matches!(e, SyncError::SkippedRest);
+                self.leave(stem, media_files);
+                return Ok(());
+            }
+            Err(e) => output::suspend(|| eprintln!("{}", e.to_string().red().bold())),
+            Ok(_) => {}
+        }
+        self.limit.record(&result);
+        self.progress.inc();
+        self.results.push((stem, result));
+        Ok(())
+    }
+
+    fn finish(self) -> Vec<FolderResult> {
+        self.results
+    }
+}
+
+/// The media of one season of the output, and the subtitle folders to match against it
+#[derive(Debug)]
+struct Season {
+    /// Name of the output season folder in multi-season mode
+    name: Option<String>,
+    /// Folder of subtitle folders, absent for output seasons without subtitles
+    input: Option<PathBuf>,
+    stems: HashMap<String, Vec<PathBuf>>,
+}
+
+impl Season {
+    /// Prefixes a stem with the season name in multi-season mode
+    fn qualify(&self, stem: &str) -> String {
+        match &self.name {
+            Some(name) => format!("{name}/{stem}"),
+            None => stem.to_string(),
+        }
+    }
+}
+
+/// Pairs the season folders and archives of `input` with the folders of `output` by name, then
+/// by season number, scanning each output season for media. Output seasons without subtitles are
+/// kept so their media is reported as unmatched. Specials seasons are left out with
+/// --exclude-specials
+fn pair_seasons(
+    input: &Path,
+    output: &Path,
+    media_extensions: &[String],
+    walk: destination::Walk,
+    staging: &archive::Staging,
+    extensions: &[String],
+    cli: &SyncArgs,
+) -> Result<Vec<Season>, SyncError> {
+    let rules = matching::MatchRules {
+        ignore_case: cli.ignore_case,
+        normalize: cli.normalize,
+    };
+    let included = |name: &str| {
+        let excluded = cli.exclude_specials && matching::is_specials(name);
+        if excluded {
+            verbose!("Skipping specials {name}");
+        }
+        !excluded
+    };
+    let subdirectories = |dir: &Path| -> Result<Vec<(String, PathBuf)>, SyncError> {
+        let mut dirs: Vec<_> = read_dir(dir)
+            .map_err(SyncError::io(dir))?
+            .flatten()
+            .filter(|e| walk.lists(&e.path()) && destination::is_dir(e, walk.follow_symlinks))
+            .map(|e| (e.file_name().to_string_lossy().to_string(), e.path()))
+            .filter(|(name, _)| included(name))
+            .collect();
+        dirs.sort_unstable();
+        Ok(dirs)
+    };
+
+    let mut inputs = subdirectories(input)?;
+    for entry in read_dir(input).map_err(SyncError::io(input))?.flatten() {
+        let path = entry.path();
+        if walk.lists(&path) && path.is_file() && archive::is_archive(&path) {
+            let name = path
+                .file_stem()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            if !included(&name) {
+                continue;
+            }
+            match staging.extract(&path, extensions) {
+                Ok(dir) => inputs.push((name, dir)),
+                Err(e) => eprintln!("{}", e.to_string().red().bold()),
+            }
+        }
+    }
+    inputs.sort_unstable();
+
+    let mut outputs = subdirectories(output)?;
+    let mut seasons = Vec::new();
+    for (name, input) in inputs {
+        let unique = |same: &dyn Fn(&str) -> bool| {
+            let mut found = outputs.iter().enumerate().filter(|(_, (o, _))| same(o));
+            match (found.next(), found.next()) {
+                (Some((i, _)), None) => Some(i),
+                _ => None,
+            }
+        };
+        let position = outputs
+            .iter()
+            .position(|(o, _)| *o == name)
+            .or_else(|| unique(&|o| rules.key(o) == rules.key(&name)))
+            .or_else(|| {
+                let number = matching::extract_season(&name)?;
+                unique(&|o| matching::extract_season(o) == Some(number))
+            });
+        let Some(position) = position else {
+            warn!(
+                "{}",
+                format!("No output season matches {name}, skipping it").yellow()
+            );
+            continue;
+        };
+        let (name, output) = outputs.remove(position);
+        seasons.push(Season {
+            name: Some(name),
+            input: Some(input),
+            stems: destination::scan(&output, media_extensions, walk)?,
+        });
+    }
+    for (name, output) in outputs {
+        seasons.push(Season {
+            name: Some(name),
+            input: None,
+            stems: destination::scan(&output, media_extensions, walk)?,
+        });
+    }
+    seasons.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+    Ok(seasons)
+}
+
+/// Pairs subtitle folders with the only media stem containing their name as a token, returning
+/// the folders left unpaired. Folders several stems contain are reported and left unmatched
+fn match_by_token(
+    dirs: Vec<(String, PathBuf)>,
+    destination_stems: &mut HashMap<String, Vec<PathBuf>>,
+    pairs: &mut Vec<Pairing>,
+) -> Vec<(String, PathBuf)> {
+    let mut unmatched = Vec::new();
+    for (dir_name, sub_dir) in dirs {
+        let mut stems: Vec<_> = destination_stems
+            .keys()
+            .filter(|stem| matching::contains_token(stem, &dir_name))
+            .cloned()
+            .collect();
+        stems.sort_unstable();
+        match stems.as_slice() {
+            [stem] => {
+                let (stem, media_files) =
+                    destination_stems.remove_entry(stem).expect("stem to exist");
+                pairs.push(Pairing {
+                    dir_name,
+                    sub_dir,
+                    stem,
+                    media_files,
+                    second_dir: None,
+                    matched_by: MatchKind::Token,
+                });
+                continue;
+            }
+            [] => {}
+            stems => info!(
+                "{}",
+                format!("Ambiguous match for {dir_name}: {}", stems.join(", ")).yellow()
+            ),
+        }
+        unmatched.push((dir_name, sub_dir));
+    }
+    unmatched
+}
+
+/// Pairs subtitle folders with media files sharing the same season and episode numbers,
+/// returning the folders left unpaired. Media covering several episodes, such as
+/// `Show.S01E01E02`, is paired with the folder of its first episode found, keeping the folder of
+/// the next as its `second_dir`. Episodes claimed by more than one folder or media file are
+/// reported and left unmatched
+fn match_by_episode(
+    dirs: Vec<(String, PathBuf)>,
+    destination_stems: &mut HashMap<String, Vec<PathBuf>>,
+    pairs: &mut Vec<Pairing>,
+) -> Vec<(String, PathBuf)> {
+    let mut stems_by_episode: HashMap<(u32, u32), Vec<String>> = HashMap::new();
+    for stem in destination_stems.keys() {
+        for episode in matching::extract_episodes(stem) {
+            stems_by_episode
+                .entry(episode)
+                .or_default()
+                .push(stem.clone());
+        }
+    }
+
+    let mut dirs_by_episode: HashMap<(u32, u32), Vec<(String, PathBuf)>> = HashMap::new();
+    let mut unmatched = Vec::new();
+    for (dir_name, path) in dirs {
+        match matching::extract_episode(&dir_name) {
+            Some(episode) => dirs_by_episode
+                .entry(episode)
+                .or_default()
+                .push((dir_name, path)),
+            None => unmatched.push((dir_name, path)),
+        }
+    }
+
+    let first_pair = pairs.len();
+    let mut episodes: Vec<_> = dirs_by_episode.into_iter().collect();
+    episodes.sort_unstable_by_key(|(episode, _)| *episode);
+    for ((season, episode), mut dirs) in episodes {
+        let stems = stems_by_episode.get(&(season, episode));
+        match stems.map(Vec::as_slice) {
+            Some([stem]) if dirs.len() == 1 => {
+                let (dir_name, sub_dir) = dirs.pop().expect("one folder");
+                if let Some((stem, media_files)) = destination_stems.remove_entry(stem) {
+                    pairs.push(Pairing {
+                        dir_name,
+                        sub_dir,
+                        stem,
+                        media_files,
+                        second_dir: None,
+                        matched_by: MatchKind::Episode,
+                    });
+                    continue;
+                }
+                // The media covers several episodes and an earlier one was already paired
+                let pairing = pairs[first_pair..]
+                    .iter_mut()
+                    .find(|p| p.stem == *stem)
+                    .expect("stem paired with an earlier episode");
+                let covered = matching::extract_episodes(&pairing.dir_name).len() > 1;
+                if pairing.second_dir.is_none() && !covered {
+                    pairing.second_dir = Some(sub_dir);
+                } else {
+                    unmatched.push((dir_name, sub_dir));
+                }
+                continue;
+            }
+            Some(stems) => {
+                let names: Vec<_> = dirs
+                    .iter()
+                    .map(|(name, _)| name.as_str())
+                    .chain(stems.iter().map(String::as_str))
+                    .collect();
+                info!(
+                    "{}",
+                    format!(
+                        "Ambiguous match for S{season:02}E{episode:02}: {}",
+                        names.join(", ")
+                    )
+                    .yellow()
+                );
+            }
+            None => {}
+        }
+        unmatched.extend(dirs);
+    }
+
+    for pairing in &pairs[first_pair..] {
+        if pairing.second_dir.is_none()
+            && matching::extract_episodes(&pairing.stem).len() > 1
+            && matching::extract_episodes(&pairing.dir_name).len() < 2
+        {
+            info!(
+                "{}",
+                format!(
+                    "Only found subtitles for one episode of {}, using {}",
+                    pairing.stem, pairing.dir_name
+                )
+                .yellow()
+            );
+        }
+    }
+
+    unmatched
+}
+
+/// Settings shared by every folder synchronized during a run
+#[derive(Debug)]
+struct SyncOptions {
+    strategy: SubtitleSelectionStrategy,
+    sort_strat: SubtitleSelectionStrategy,
+    /// Flip the sort order so selection picks from the other end
+    reverse: bool,
+    /// Compare names with `matching::natural_cmp` instead of bytewise
+    natural_sort: bool,
+    link_mode: LinkMode,
+    /// Fail when a symlink can't be created instead of copying
+    no_fallback: bool,
+    overwrite: bool,
+    /// Report targets already providing the source as unchanged instead of replacing them
+    skip_existing: bool,
+    /// Write a provenance sidecar next to each placed subtitle
+    sidecar: bool,
+    /// Lowercase keywords that must all appear in a subtitle's name
+    required_text: Vec<String>,
+    /// Keyword and strategy overrides for particular subtitle folders
+    rules: Option<rules::Rules>,
+    /// Media finished by earlier runs with --resume, and where this run's progress is recorded
+    progress: Option<resume::Progress>,
+    /// How folders and subtitle files are compared to media stems
+    match_rules: matching::MatchRules,
+    /// Expression subtitle names must match in addition to `required_text`
+    keyword_regex: Option<Regex>,
+    /// Lowercase subtitle extensions without the leading dot
+    extensions: Vec<String>,
+    /// Lowercase extension given to targets instead of the source's own
+    out_ext: Option<String>,
+    /// Convert between SRT and WebVTT when `out_ext` names the other format
+    convert: bool,
+    dry_run: bool,
+    relative: bool,
+    /// Link media into the subtitle's folder instead of the subtitle next to the media
+    reverse_link: bool,
+    /// Language code to tag targets with, overriding any detected from the source
+    lang: Option<String>,
+    /// Format of targets, replacing `<stem>.<lang>.<ext>`
+    template: Option<template::Template>,
+    /// Place every candidate instead of selecting one
+    all: bool,
+    /// Select a subtitle from each language folder instead of the folder itself
+    lang_dirs: bool,
+    /// Convert copied subtitles to UTF-8
+    transcode: bool,
+    /// Line endings to convert copied text subtitles to, also stripping their byte order mark
+    eol: Option<Eol>,
+    /// Check copies against their source after writing them
+    verify: bool,
+    /// Give copies the modification time of their source
+    preserve_mtime: bool,
+    /// Hardlink copies of contents already copied to the first copy
+    dedup: bool,
+    /// Targets copied so far by the checksum of their contents, for `dedup`
+    copies: Mutex<HashMap<Vec<u8>, PathBuf>>,
+    /// Subtitles embedded in the media, with --check-embedded and ffprobe installed
+    embedded: Option<embedded::Embedded>,
+    /// Replace existing files without confirmation
+    yes: bool,
+    non_interactive: bool,
+    /// How to treat SRT files failing validation, if they're validated at all
+    validate: Option<Severity>,
+    /// Subtitles smaller than this many bytes are ignored
+    min_size: Option<u64>,
+    /// Subtitles larger than this many bytes are ignored
+    max_size: Option<u64>,
+    include_symlinks: bool,
+    /// Consider files and folders whose name starts with a dot
+    include_hidden: bool,
+    /// Levels of folders below each subtitle folder searched for subtitles
+    sub_depth: usize,
+    /// Rename replaced files to a backup instead of deleting them
+    backup: bool,
+    /// Records filesystem changes for `subsync undo`, absent during dry runs
+    journal: Option<journal::Journal>,
+    /// Learn a name pattern from each manual selection to select from later folders with
+    learn: bool,
+    /// Pattern of the last manual selection while learning
+    learned: Mutex<Option<matching::NamePattern>>,
+    /// Timing offset in milliseconds to apply instead of linking
+    shift: Option<i64>,
+    /// Write the subtitles of both episodes of double-episode media into one file
+    merge: bool,
+    /// Where archives found in the input are extracted for the length of the run
+    staging: archive::Staging,
+    /// Directory targets are placed in instead of next to their media
+    sub_out: Option<PathBuf>,
+    /// Directory whose folders are recreated inside `sub_out`
+    mirrored: Option<PathBuf>,
+    /// Create missing target folders instead of failing
+    mkdir: bool,
+}
+
+impl SyncOptions {
+    /// Options from the command line, selecting alphabetically without keywords. The journal is
+    /// kept in `media_root`, the directory the media is found in, unless --sub-out is given
+    fn new(
+        cli: &SyncArgs,
+        link_mode: LinkMode,
+        extensions: Vec<String>,
+        media_root: &Path,
+    ) -> Self {
+        let journal_dir = cli.sub_out.as_deref().unwrap_or(media_root);
+        Self {
+            strategy: SubtitleSelectionStrategy::Alphabetical,
+            sort_strat: SubtitleSelectionStrategy::Alphabetical,
+            reverse: cli.reverse,
+            natural_sort: cli.natural_sort,
+            link_mode,
+            no_fallback: cli.no_fallback,
+            overwrite: cli.overwrite,
+            skip_existing: cli.skip_existing,
+            sidecar: cli.sidecar,
+            required_text: Vec::new(),
+            rules: None,
+            progress: None,
+            keyword_regex: cli.keyword_regex.clone(),
+            match_rules: matching::MatchRules {
+                ignore_case: cli.ignore_case,
+                normalize: cli.normalize,
+            },
+            extensions,
+            out_ext: cli
+                .out_ext
+                .as_ref()
+                .map(|e| e.trim().trim_start_matches('.').to_lowercase())
+                .filter(|e| !e.is_empty()),
+            convert: cli.convert,
+            dry_run: cli.dry_run,
+            relative: cli.relative,
+            reverse_link: cli.reverse_link,
+            lang: cli.lang.as_ref().map(|l| l.to_lowercase()),
+            template: cli.template.clone(),
+            all: cli.all,
+            lang_dirs: cli.lang_dirs,
+            transcode: cli.transcode,
+            eol: cli.normalize_text.then(|| cli.eol.unwrap_or(Eol::Lf)),
+            verify: cli.verify,
+            preserve_mtime: cli.preserve_mtime,
+            dedup: cli.dedup,
+            copies: Mutex::new(HashMap::new()),
+            embedded: (cli.check_embedded || cli.skip_embedded)
+                .then(|| embedded::Embedded::new(cli.skip_embedded))
+                .flatten(),
+            yes: cli.yes,
+            non_interactive: cli.non_interactive,
+            backup: cli.backup,
+            min_size: cli.min_size,
+            max_size: cli.max_size,
+            include_symlinks: cli.include_symlinks,
+            include_hidden: cli.include_hidden,
+            sub_depth: cli.sub_depth.unwrap_or(0),
+            validate: cli.validate,
+            journal: (!cli.dry_run).then(|| journal::Journal::open(journal_dir)),
+            shift: cli.shift,
+            merge: cli.merge,
+            learn: cli.learn,
+            learned: Mutex::new(None),
+            staging: archive::Staging::new(),
+            sub_out: cli.sub_out.clone(),
+            mirrored: cli.mirror.then(|| media_root.to_path_buf()),
+            mkdir: cli.mkdir,
+        }
+    }
+
+    /// The `--rules` entry applying to the subtitle folder `sub_dir`, if any
+    fn rule(&self, sub_dir: &Path) -> Option<&rules::Rule> {
+        self.rules.as_ref()?.find(sub_dir)
+    }
+
+    /// The strategy selecting among the subtitles of `sub_dir` and the order they're sorted in
+    /// first. A rule's preference strategy falls back like the run's own if that is one too,
+    /// and alphabetically otherwise
+    fn selection(&self, sub_dir: &Path) -> (SubtitleSelectionStrategy, SubtitleSelectionStrategy) {
+        match self.rule(sub_dir).and_then(|r| r.strategy) {
+            None => (self.strategy, self.sort_strat),
+            Some(strategy) if strategy.is_preference() && self.strategy.is_preference() => {
+                (strategy, self.sort_strat)
+            }
+            Some(strategy)
+                if strategy.is_preference()
+                    || matches!(strategy, SubtitleSelectionStrategy::Manual) =>
+            {
+                (strategy, SubtitleSelectionStrategy::Alphabetical)
+            }
+            Some(strategy) => (strategy, strategy),
+        }
+    }
+
+    /// Orders two names for sorting, naturally with --natural-sort and bytewise otherwise
+    fn compare_names(&self, a: &str, b: &str) -> Ordering {
+        if self.natural_sort {
+            matching::natural_cmp(a, b)
+        } else {
+            a.cmp(b)
+        }
+    }
+
+    /// Orders candidates by name, then by the folders they're listed under
+    fn compare_candidates(&self, a: &Candidate, b: &Candidate) -> Ordering {
+        self.compare_names(&a.name, &b.name)
+            .then_with(|| self.compare_names(&a.listed, &b.listed))
+    }
+
+    /// Whether an earlier run with --resume finished the media sharing `stem`
+    fn resumes(&self, stem: &str, media_files: &[PathBuf]) -> bool {
+        let done = self
+            .progress
+            .as_ref()
+            .is_some_and(|progress| progress.is_done(media_files));
+        if done {
+            verbose!("Skipping {stem}, it was finished earlier");
+        }
+        done
+    }
+}
+
+/// What was done to provide a subtitle for a single media file
+#[derive(Debug)]
+struct SyncOutcome {
+    source: PathBuf,
+    /// Size of the source subtitle in bytes
+    size: u64,
+    target: PathBuf,
+    operation: &'static str,
+    status: Status,
+    /// The target was copied because the symlink couldn't be created
+    fell_back: bool,
+    /// The target was hardlinked to an identical copy under --dedup instead of copied
+    deduplicated: bool,
+    /// Strategy that selected the subtitle, including any it fell back to
+    selected_by: Option<String>,
+    /// Where the file replaced at the target was moved with --backup
+    backup: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Status {
+    /// The target didn't exist before
+    Created,
+    /// The target already provided the subtitle
+    Unchanged,
+    /// An existing file at the target was replaced
+    Replaced,
+    /// The target was left untouched
+    Skipped,
+}
+
+impl SyncOutcome {
+    /// How the status line of the outcome is marked and the action it names, such as `copied`,
+    /// or `would copy` in a dry run
+    fn status_action(&self, dry_run: bool) -> (output::Mark, String) {
+        let done = match self.operation {
+            "symlink" => "symlinked",
+            "hardlink" => "hardlinked",
+            "copy" => "copied",
+            "merge" => "merged",
+            "shift" => "shifted",
+            "convert" => "converted",
+            operation => operation,
+        };
+        match self.status {
+            Status::Created if dry_run => (output::Mark::Done, format!("would {}", self.operation)),
+            Status::Created => (output::Mark::Done, done.to_string()),
+            Status::Unchanged => (output::Mark::Done, "unchanged".to_string()),
+            Status::Replaced if dry_run => (output::Mark::Replaced, "would replace".to_string()),
+            Status::Replaced => (output::Mark::Replaced, "replaced".to_string()),
+            Status::Skipped => (output::Mark::Skipped, "skipped".to_string()),
+        }
+    }
+}
+
+/// Whether the subtitle is in a format stored as text, whose contents may be normalized
+fn is_text(path: &Path) -> bool {
+    path.extension().is_some_and(|e| {
+        ["srt", "ass", "ssa", "vtt", "smi", "sami", "txt"]
+            .contains(&e.to_string_lossy().to_lowercase().as_str())
+    })
+}
+
+/// Copies a subtitle, converting it to UTF-8 when `transcode` is set and it's in another
+/// encoding, then stripping its byte order mark and converting its line endings to `eol` if
+/// given. Falls back to a raw copy when the text can't be decoded
+fn text_copy(
+    source: &Path,
+    target: &Path,
+    transcode: bool,
+    eol: Option<Eol>,
+) -> Result<(), SyncError> {
+    let bytes = read(source).map_err(SyncError::io(source))?;
+    let text = match encoding::to_utf8(&bytes) {
+        Some(encoding::Decoded::Utf8(text)) => Some(text.to_string()),
+        Some(encoding::Decoded::Transcoded(text, from)) if transcode => {
+            info!(
+                "Transcoded {} from {}",
+                source.to_string_lossy(),
+                from.name()
+            );
+            Some(text)
+        }
+        _ => {
+            info!(
+                "{}",
+                format!(
+                    "Couldn't {} {}, copying as-is",
+                    if transcode {
+                        "detect the encoding of"
+                    } else {
+                        "normalize non-UTF-8"
+                    },
+                    source.to_string_lossy()
+                )
+                .yellow()
+            );
+            None
+        }
+    };
+    match (text, eol) {
+        (Some(text), Some(eol)) => write(target, encoding::normalize(&text, eol.as_str())),
+        (Some(text), None) => write(target, text),
+        (None, _) => write(target, &bytes),
+    }
+    .map_err(SyncError::io(target))
+}
+
+/// Names of the subtitle files up to three folders deep in `input`, enough to cover every mode.
+/// Hidden files and folders are only included with `include_hidden`
+fn subtitle_names(input: &Path, extensions: &[String], include_hidden: bool) -> Vec<String> {
+    let mut names = Vec::new();
+    let mut pending = vec![(input.to_path_buf(), 0)];
+    while let Some((dir, depth)) = pending.pop() {
+        let Ok(entries) = read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !include_hidden && destination::is_hidden(&path) {
+                continue;
+            }
+            if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                if depth < 2 {
+                    pending.push((path, depth + 1));
+                }
+            } else if has_extension(&path, extensions) {
+                if let Some(stem) = path.file_stem() {
+                    names.push(stem.to_string_lossy().to_string());
+                }
+            }
+        }
+    }
+    names
+}
+
+/// Asks for the keywords subtitle names must contain, first offering the words that set some
+/// of the input's subtitles apart from the rest, then falling back to free text
+fn prompt_keywords(
+    input: &Path,
+    extensions: &[String],
+    include_hidden: bool,
+) -> Result<Vec<String>, InquireError> {
+    let suggestions =
+        matching::keyword_suggestions(&subtitle_names(input, extensions, include_hidden), 10);
+    if !suggestions.is_empty() {
+        let options = suggestions
+            .iter()
+            .map(|(word, count)| format!("{word} ({count} files)"))
+            .collect();
+        let chosen = output::prompt(
+            "Select keywords to require (optional):",
+            Vec::new(),
+            |message| MultiSelect::new(message, options).raw_prompt(),
+        )?;
+        if !chosen.is_empty() {
+            return Ok(chosen
+                .into_iter()
+                .map(|choice| suggestions[choice.index].0.clone())
+                .collect());
+        }
+    }
+    Ok(vec![output::prompt(
+        "Enter subtitle file name keyword (optional):",
+        String::new(),
+        |message| Text::new(message).prompt(),
+    )?])
+}
+
+/// Lowercases extensions and strips leading dots, dropping empty ones
+fn normalize_extensions(extensions: &[String]) -> Vec<String> {
+    extensions
+        .iter()
+        .map(|e| e.trim().trim_start_matches('.').to_lowercase())
+        .filter(|e| !e.is_empty())
+        .collect()
+}
+
+/// Whether an input entry is a folder of subtitles or an archive standing in for one
+fn is_folder(entry: &DirEntry, follow_symlinks: bool) -> bool {
+    destination::is_dir(entry, follow_symlinks) || archive::is_archive(&entry.path())
+}
+
+/// Stands for files without an extension in a list of extensions, such as `--media-ext mkv,none`
+const NO_EXTENSION: &str = "none";
+
+/// Whether the path has one of the given (lowercase) extensions
+fn has_extension(path: &Path, extensions: &[String]) -> bool {
+    path.extension()
+        .is_some_and(|e| extensions.contains(&e.to_string_lossy().to_lowercase()))
+}
+
+/// Whether the path has one of the given (lowercase) media extensions, or none at all when they
+/// include [`NO_EXTENSION`]
+fn is_media(path: &Path, media_extensions: &[String]) -> bool {
+    match path.extension() {
+        Some(_) => has_extension(path, media_extensions),
+        None => media_extensions.iter().any(|e| e == NO_EXTENSION),
+    }
+}
+
+/// Extensions of the subtitle formats whose cues can be read and rewritten
+const TIMED_EXTENSIONS: [&str; 2] = ["srt", "vtt"];
+
+/// Whether --convert rewrites subtitles with the extension `from` into the format of `to`
+fn converts(from: &str, to: &str) -> bool {
+    from != to && TIMED_EXTENSIONS.contains(&from) && TIMED_EXTENSIONS.contains(&to)
+}
+
+/// A subtitle file eligible for selection
+#[derive(Debug, Clone)]
+struct Candidate {
+    path: PathBuf,
+    name: String,
+    /// Name with the folders between the subtitle folder and the file, such as
+    /// `Forced/episode.srt`, so files of the same name can be told apart
+    listed: String,
+    size: u64,
+    /// Modification time, absent on filesystems that don't record one
+    modified: Option<SystemTime>,
+    /// Number of cues, parsed the first time it's needed and absent for unparsable files
+    cues: OnceLock<Option<usize>>,
+}
+
+impl Candidate {
+    fn cues(&self) -> Option<usize> {
+        *self.cues.get_or_init(|| {
+            has_extension(&self.path, &["srt".to_string()])
+                .then(|| srt::cue_count(&self.path).ok())
+                .flatten()
+        })
+    }
+
+    /// Writes the name and size, with the cue count and language from the name when `detailed`,
+    /// then the age
+    fn describe(&self, f: &mut std::fmt::Formatter<'_>, detailed: bool) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({}",
+            self.listed,
+            humansize::format_size(self.size, DECIMAL)
+        )?;
+        if detailed {
+            if let Some(cues) = self.cues() {
+                write!(f, ", {cues} {}", if cues == 1 { "cue" } else { "cues" })?;
+            }
+            if let Some(lang) = lang::suffix(&self.name) {
+                write!(f, ", {lang}")?;
+            }
+        }
+        if let Some(age) = self.modified.and_then(|m| m.elapsed().ok()) {
+            write!(f, ", modified {}", format_age(age))?;
         }
+        f.write_str(")")
     }
-    Ok(())
 }
 
-#[derive(Debug)]
-struct ManualSelectionData {
-    name: String,
-    size: u64,
+/// Detailed with the alternate flag, such as `{:#}`
+impl Display for Candidate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.describe(f, f.alternate())
+    }
+}
+
+/// Describes how long ago something happened in its largest whole unit, such as `3 days ago`
+fn format_age(age: Duration) -> String {
+    let seconds = age.as_secs();
+    let (count, unit) = match seconds {
+        0..60 => return "just now".to_string(),
+        60..3600 => (seconds / 60, "minute"),
+        3600..86400 => (seconds / 3600, "hour"),
+        _ => (seconds / 86400, "day"),
+    };
+    format!("{count} {unit}{} ago", if count == 1 { "" } else { "s" })
+}
+
+/// An entry of the manual selection prompt
+enum ManualChoice<'a> {
+    Subtitle(&'a Candidate),
+    /// Leave the media without a subtitle
+    Skip,
+    /// Leave this and every later media without a subtitle, ending the prompts
+    SkipRest,
 }
 
-impl Display for ManualSelectionData {
+impl Display for ManualChoice<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_str(&format!(
-            "{} ({})",
-            self.name,
-            humansize::format_size(self.size, DECIMAL)
-        ))
+        match self {
+            Self::Subtitle(candidate) => candidate.describe(f, true),
+            Self::Skip => f.write_str("Skip this episode"),
+            Self::SkipRest => f.write_str("Skip all remaining"),
+        }
     }
 }
 
-fn synchronize_folder(
-    sub_dir: &PathBuf,
-    dest_file: &PathBuf,
-    strategy: SubtitleSelectionStrategy,
-    sort_strat: SubtitleSelectionStrategy,
-    copy_sub: bool,
-    overwrite: bool,
-    required_text: &Option<String>,
-) -> Result<(), Box<dyn Error>> {
-    let mut subtitle_files: Vec<_> = read_dir(sub_dir)?
+/// Collects the subtitle files in `sub_dir` and the folders up to `--sub-depth` below it, and
+/// those in any archives there, matching the extension and keyword filters, skipping any whose
+/// metadata can't be read
+fn collect_candidates(sub_dir: &Path, options: &SyncOptions) -> Result<Vec<Candidate>, SyncError> {
+    let required_text = match options.rule(sub_dir).and_then(|r| r.keyword.as_ref()) {
+        Some(keyword) => std::slice::from_ref(keyword),
+        None => options.required_text.as_slice(),
+    };
+    // Each path is kept with the folder it's listed relative to
+    let mut paths = Vec::new();
+    let mut pending = vec![(sub_dir.to_path_buf(), 0)];
+    while let Some((dir, depth)) = pending.pop() {
+        for entry in read_dir(&dir).map_err(SyncError::io(&dir))?.flatten() {
+            let path = entry.path();
+            if !options.include_hidden && destination::is_hidden(&path) {
+                debug!("Skipping {}: hidden", path.to_string_lossy());
+                continue;
+            }
+            if archive::is_archive(&path) && path.is_file() {
+                let extracted = options.staging.extract(&path, &options.extensions)?;
+                let base = extracted.parent().unwrap_or(&extracted).to_path_buf();
+                for file in destination::files(
+                    &extracted,
+                    destination::Walk {
+                        recursive: true,
+                        follow_symlinks: false,
+                        include_hidden: options.include_hidden,
+                    },
+                )? {
+                    paths.push((file, base.clone()));
+                }
+            } else if entry.file_type().is_ok_and(|t| t.is_dir()) {
+                if depth < options.sub_depth {
+                    pending.push((path, depth + 1));
+                }
+            } else {
+                paths.push((path, sub_dir.to_path_buf()));
+            }
+        }
+    }
+
+    let mut candidates = Vec::new();
+    for (path, base) in paths {
+        let listed = path
+            .strip_prefix(&base)
+            .unwrap_or(&path)
+            .to_string_lossy()
+            .to_string();
+        let name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        if !has_extension(&path, &options.extensions) {
+            debug!("Skipping {}: not a subtitle", path.to_string_lossy());
+            continue;
+        }
+        let lower = name.to_lowercase();
+        if !required_text.iter().all(|rt| lower.contains(rt)) {
+            debug!("Skipping {}: missing keyword", path.to_string_lossy());
+            continue;
+        }
+        if options
+            .keyword_regex
+            .as_ref()
+            .is_some_and(|re| !re.is_match(&name))
+        {
+            debug!("Skipping {}: doesn't match regex", path.to_string_lossy());
+            continue;
+        }
+        if !options.include_symlinks && path.is_symlink() {
+            debug!("Skipping {}: symlink", path.to_string_lossy());
+            continue;
+        }
+
+        match metadata(&path) {
+            Ok(metadata) if options.min_size.is_some_and(|min| metadata.len() < min) => {
+                debug!("Skipping {}: too small", path.to_string_lossy());
+            }
+            Ok(metadata) if options.max_size.is_some_and(|max| metadata.len() > max) => {
+                debug!("Skipping {}: too large", path.to_string_lossy());
+            }
+            Ok(metadata) => candidates.push(Candidate {
+                path,
+                name,
+                listed,
+                size: metadata.len(),
+                modified: metadata.modified().ok(),
+                cues: OnceLock::new(),
+            }),
+            Err(e) => warn!(
+                "{}",
+                format!(
+                    "Skipping unreadable subtitle {}: {e}",
+                    path.to_string_lossy()
+                )
+                .yellow()
+            ),
+        }
+    }
+    Ok(candidates)
+}
+
+/// The subtitles selected for a folder and the targets they'll be placed at
+#[derive(Debug)]
+struct FolderPlan {
+    placements: Vec<Placement>,
+}
+
+/// Selects among the subtitles in `sub_dir` for each of `dest_files` without changing anything
+fn plan_folder(
+    sub_dir: &Path,
+    dest_files: &[PathBuf],
+    options: &SyncOptions,
+) -> Result<FolderPlan, SyncError> {
+    if options.lang_dirs {
+        return plan_lang_dirs(sub_dir, dest_files, options);
+    }
+    let subtitle_files = collect_candidates(sub_dir, options)?;
+    verbose!("Considering in {}:", sub_dir.to_string_lossy());
+    for sub in &subtitle_files {
+        verbose!(" - {sub}");
+    }
+    plan_candidates(
+        sub_dir,
+        subtitle_files,
+        dest_files,
+        options.lang.clone(),
+        options,
+    )
+}
+
+/// Plans each folder of `sub_dir` named after a language as its own subtitle folder, tagging
+/// the subtitle selected there with that language. Only the first folder of each language is used
+fn plan_lang_dirs(
+    sub_dir: &Path,
+    dest_files: &[PathBuf],
+    options: &SyncOptions,
+) -> Result<FolderPlan, SyncError> {
+    let mut dirs: Vec<_> = read_dir(sub_dir)
+        .map_err(SyncError::io(sub_dir))?
         .flatten()
-        .filter(|de| {
-            de.path().extension().map(|e| e == "srt").unwrap_or(false)
-                && (required_text.is_none()
-                    || required_text.as_ref().is_some_and(|rt| {
-                        de.file_name().to_string_lossy().to_lowercase().contains(rt)
-                    }))
-        })
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|entry| entry.path())
+        .filter(|path| options.include_hidden || !destination::is_hidden(path))
+        .collect();
+    dirs.sort_unstable();
+
+    let mut languages = HashSet::new();
+    let mut placements = Vec::new();
+    for dir in dirs {
+        let name = dir.file_name().unwrap_or_default().to_string_lossy();
+        let Some(code) = lang::from_folder(&name) else {
+            verbose!("Skipping {}: not a language", dir.to_string_lossy());
+            continue;
+        };
+        if !languages.insert(code.clone()) {
+            warn!(
+                "{}",
+                format!(
+                    "Skipping {}: another folder is already {code}",
+                    dir.to_string_lossy()
+                )
+                .yellow()
+            );
+            continue;
+        }
+        let subtitle_files = collect_candidates(&dir, options)?;
+        if subtitle_files.is_empty() {
+            verbose!("Skipping {}: no subtitles", dir.to_string_lossy());
+            continue;
+        }
+        verbose!("Considering in {} ({code}):", dir.to_string_lossy());
+        for sub in &subtitle_files {
+            verbose!(" - {sub}");
+        }
+        let plan = plan_candidates(&dir, subtitle_files, dest_files, Some(code), options)?;
+        placements.extend(plan.placements);
+    }
+
+    if placements.is_empty() {
+        return Err(SyncError::NoSubtitles {
+            dir: sub_dir.to_path_buf(),
+            min_size: options.min_size,
+            max_size: options.max_size,
+        });
+    }
+    Ok(FolderPlan { placements })
+}
+
+/// Plans the folder of `pairing`, and with --merge the folder of its second episode too,
+/// appending the subtitle selected there to each placement
+fn plan_pairing(pairing: &Pairing, options: &SyncOptions) -> Result<FolderPlan, SyncError> {
+    let mut plan = plan_folder(&pairing.sub_dir, &pairing.media_files, options)?;
+    if let Some(second_dir) = pairing.second_dir.as_ref().filter(|_| options.merge) {
+        let second = plan_folder(second_dir, &pairing.media_files, options)?;
+        for (placement, next) in plan.placements.iter_mut().zip(second.placements) {
+            placement.appended = Some(next.source);
+        }
+    }
+    Ok(plan)
+}
+
+/// Places every subtitle of `plan` at its target, stopping at the first failure
+fn execute_plan(plan: &FolderPlan, options: &SyncOptions) -> Result<Vec<SyncOutcome>, SyncError> {
+    plan.placements
+        .iter()
+        .map(|placement| place_subtitle(placement, options))
+        .collect()
+}
+
+/// Executes `plan`, then records `media_files` as finished for --resume
+fn execute_tracked(
+    plan: &FolderPlan,
+    media_files: &[PathBuf],
+    options: &SyncOptions,
+) -> Result<Vec<SyncOutcome>, SyncError> {
+    let outcomes = execute_plan(plan, options)?;
+    if let Some(progress) = options.progress.as_ref().filter(|_| !options.dry_run) {
+        progress.complete(media_files)?;
+    }
+    Ok(outcomes)
+}
+
+/// Selects among the subtitles found in `sub_dir`, prompting if needed, and decides where each
+/// is placed next to `dest_files` without touching the filesystem. Media files in the same
+/// directory share their targets, so only the first of them is used. Targets are tagged with
+/// `lang`, or otherwise a language detected in the subtitle's name
+fn plan_candidates(
+    sub_dir: &Path,
+    mut subtitle_files: Vec<Candidate>,
+    dest_files: &[PathBuf],
+    lang: Option<String>,
+    options: &SyncOptions,
+) -> Result<FolderPlan, SyncError> {
+    let mut parents = HashSet::new();
+    let dest_files: Vec<_> = dest_files
+        .iter()
+        .filter(|file| parents.insert(file.parent()))
         .collect();
 
     if subtitle_files.is_empty() {
-        eprintln!(
-            "{} {}",
-            "No subtitles in sub directory ".red().bold(),
-            sub_dir
-                .file_name()
-                .expect("filename")
-                .to_string_lossy()
-                .red()
-                .bold()
-        );
-        return Err("Empty subtitle dir".into());
+        return Err(SyncError::NoSubtitles {
+            dir: sub_dir.to_path_buf(),
+            min_size: options.min_size,
+            max_size: options.max_size,
+        });
+    }
+
+    if options.all {
+        subtitle_files.sort_unstable_by(|a, b| options.compare_candidates(a, b));
+        let mut placements = Vec::new();
+        for dest_file in dest_files {
+            let mut used_targets = HashSet::new();
+            for sub in &subtitle_files {
+                let suffix = lang::suffix(&sub.name).or_else(|| lang.clone());
+                placements.push(Placement::new(
+                    sub,
+                    dest_file,
+                    suffix,
+                    &mut used_targets,
+                    options,
+                )?);
+            }
+        }
+        return Ok(FolderPlan { placements });
     }
 
+    let (strategy, sort_strat) = options.selection(sub_dir);
     match sort_strat {
         SubtitleSelectionStrategy::Alphabetical => {
-            subtitle_files.sort_unstable_by_key(|entry| entry.file_name());
+            subtitle_files.sort_unstable_by(|a, b| options.compare_candidates(a, b));
         }
         SubtitleSelectionStrategy::Size => {
-            subtitle_files
-                .sort_unstable_by_key(|entry| entry.metadata().expect("file metadata").size());
+            subtitle_files.sort_unstable_by_key(|c| c.size);
+        }
+        SubtitleSelectionStrategy::Duration => {
+            let mut timed = Vec::new();
+            for candidate in subtitle_files.drain(..) {
+                let end = if has_extension(&candidate.path, &["srt".to_string()]) {
+                    srt::end_time(&candidate.path).map_err(|issue| issue.to_string())
+                } else {
+                    Err("only SRT subtitles have a duration".to_string())
+                };
+                match end {
+                    Ok(end) => timed.push((end, candidate)),
+                    Err(e) => warn!(
+                        "{}",
+                        format!("Skipping {}: {e}", candidate.path.to_string_lossy()).yellow()
+                    ),
+                }
+            }
+            if timed.is_empty() {
+                return Err(SyncError::NoSubtitles {
+                    dir: sub_dir.to_path_buf(),
+                    min_size: options.min_size,
+                    max_size: options.max_size,
+                });
+            }
+            timed.sort_by_key(|(end, _)| *end);
+            subtitle_files = timed.into_iter().map(|(_, candidate)| candidate).collect();
+        }
+        SubtitleSelectionStrategy::Newest => {
+            if subtitle_files.iter().all(|c| c.modified.is_some()) {
+                subtitle_files.sort_unstable_by_key(|c| c.modified);
+            } else {
+                warn!(
+                    "{}",
+                    format!(
+                        "Modification times aren't available in {}, selecting by name",
+                        sub_dir.to_string_lossy()
+                    )
+                    .yellow()
+                );
+                // The newest is taken from the end, so the first by name goes last
+                subtitle_files.sort_unstable_by(|a, b| options.compare_candidates(b, a));
+            }
         }
-        SubtitleSelectionStrategy::Manual => unreachable!(),
+        _ => unreachable!("only alphabetical, size, duration, and newest sort"),
+    }
+    if options.reverse {
+        subtitle_files.reverse();
+    }
+    if subtitle_files.len() > 1 {
+        verbose!(
+            "Sort order: {}",
+            subtitle_files
+                .iter()
+                .map(|c| c.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
     }
 
+    let dest_name = dest_files[0]
+        .file_name()
+        .ok_or_else(|| SyncError::InvalidPath(dest_files[0].to_path_buf()))?;
+
+    let mut selected_by = strategy.name();
     let source_sub = match strategy {
         SubtitleSelectionStrategy::Alphabetical => {
             subtitle_files.first().expect("must be at least one entry")
         }
-        SubtitleSelectionStrategy::Size => {
+        SubtitleSelectionStrategy::Size
+        | SubtitleSelectionStrategy::Duration
+        | SubtitleSelectionStrategy::Newest => {
             subtitle_files.last().expect("must be at least one entry")
         }
-        SubtitleSelectionStrategy::Manual => {
-            let choices = subtitle_files
-                .iter()
-                .into_iter()
-                .map(|de| ManualSelectionData {
-                    name: de.file_name().to_string_lossy().to_string(),
-                    size: de.metadata().expect("file metadata").size(),
-                })
-                .collect();
+        SubtitleSelectionStrategy::Manual => 'manual: {
+            let mut learned = options.learned.lock().expect("learned pattern lock");
+            if let Some(pattern) = learned.as_ref() {
+                let matching: Vec<_> = subtitle_files
+                    .iter()
+                    .filter(|c| pattern.matches(&c.name))
+                    .collect();
+                if let [candidate] = matching[..] {
+                    info!("Selected {} matching {}", candidate.name, pattern);
+                    break 'manual candidate;
+                }
+            }
 
-            let choice = Select::new(
+            let mut choices: Vec<_> = subtitle_files.iter().map(ManualChoice::Subtitle).collect();
+            choices.extend([ManualChoice::Skip, ManualChoice::SkipRest]);
+            let choice = output::prompt(
                 &format!(
                     "Select a subtitle file for {}:",
-                    dest_file
-                        .file_name()
-                        .expect("file name")
-                        .to_string_lossy()
-                        .bold()
+                    dest_name.to_string_lossy().bold()
                 ),
-                choices,
-            )
-            .prompt()?;
+                ManualChoice::Skip,
+                |message| Select::new(message, choices).prompt(),
+            )?;
 
-            subtitle_files
+            match choice {
+                ManualChoice::Subtitle(candidate) => {
+                    if options.learn {
+                        let pattern = matching::NamePattern::learn(&candidate.name);
+                        verbose!("Learned pattern {pattern}");
+                        *learned = Some(pattern);
+                    }
+                    candidate
+                }
+                ManualChoice::Skip => return Err(SyncError::Skipped),
+                ManualChoice::SkipRest => return Err(SyncError::SkippedRest),
+            }
+        }
+        preference => {
+            let preferred: Vec<_> = subtitle_files
                 .iter()
-                .find(|de| de.file_name().to_string_lossy() == choice.name)
-                .expect("must be a selected choice")
+                .filter(|c| preference.prefers(&c.name))
+                .collect();
+            let pool = if preferred.is_empty() {
+                verbose!(
+                    "No subtitle suits the {} strategy, using {}",
+                    preference.name(),
+                    sort_strat.name()
+                );
+                selected_by = format!("{}, fell back to {}", preference.name(), sort_strat.name());
+                subtitle_files.iter().collect()
+            } else {
+                preferred
+            };
+            let chosen = match sort_strat {
+                SubtitleSelectionStrategy::Size => pool.last(),
+                _ => pool.first(),
+            };
+            *chosen.expect("must be at least one entry")
         }
     };
 
-    let target_name = dest_file
-        .parent()
-        .expect("dest file to have parent")
-        .join(format!(
-            "{}.srt",
-            &dest_file
-                .file_stem()
-                .expect("dest file stem")
-                .to_string_lossy()
-        ));
+    let lang = lang.or_else(|| lang::detect(&source_sub.name));
+    let placements = dest_files
+        .into_iter()
+        .map(|dest_file| {
+            Ok(Placement {
+                // A rule may have replaced the strategy of the run
+                selected_by: Some(selected_by.clone()),
+                ..Placement::new(
+                    source_sub,
+                    dest_file,
+                    lang.clone(),
+                    &mut HashSet::new(),
+                    options,
+                )?
+            })
+        })
+        .collect::<Result<_, SyncError>>()?;
+    Ok(FolderPlan { placements })
+}
 
-    if target_name.exists() && overwrite {
-        println!(
+/// A subtitle selected for a media file and the target it will be placed at
+#[derive(Debug)]
+struct Placement {
+    source: Candidate,
+    target: PathBuf,
+    /// Subtitle of the next episode written after the source's cues with --merge
+    appended: Option<Candidate>,
+    /// Name of the strategy that selected the source, recorded in its sidecar
+    selected_by: Option<String>,
+    /// File the target provides: the source, or the media file with --reverse-link
+    linked: PathBuf,
+    /// Media file the subtitle is placed for
+    media: PathBuf,
+}
+
+impl Placement {
+    /// Targets `source_sub` next to `dest_file`, or in --sub-out, inserting `suffix` before the
+    /// extension. A numeric disambiguator is added when the target was already produced for
+    /// another subtitle. With --reverse-link, `dest_file` is targeted next to `source_sub` under
+    /// its own name instead
+    fn new(
+        source_sub: &Candidate,
+        dest_file: &Path,
+        suffix: Option<String>,
+        used_targets: &mut HashSet<PathBuf>,
+        options: &SyncOptions,
+    ) -> Result<Self, SyncError> {
+        let (target, linked) = if options.reverse_link {
+            if options.staging.contains(&source_sub.path) {
+                return Err(SyncError::Extracted(source_sub.path.clone()));
+            }
+            let (Some(sub_parent), Some(media_name)) =
+                (source_sub.path.parent(), dest_file.file_name())
+            else {
+                return Err(SyncError::InvalidPath(dest_file.to_path_buf()));
+            };
+            (sub_parent.join(media_name), dest_file.to_path_buf())
+        } else {
+            (
+                target_path(source_sub, dest_file, suffix, used_targets, options)?,
+                source_sub.path.clone(),
+            )
+        };
+        Ok(Self {
+            source: source_sub.clone(),
+            target,
+            appended: None,
+            selected_by: Some(if options.all {
+                "all".to_string()
+            } else {
+                options.strategy.name()
+            }),
+            linked,
+            media: dest_file.to_path_buf(),
+        })
+    }
+}
+
+/// Builds the target of `source_sub` for `dest_file`, see [`Placement::new`]
+fn target_path(
+    source_sub: &Candidate,
+    dest_file: &Path,
+    suffix: Option<String>,
+    used_targets: &mut HashSet<PathBuf>,
+    options: &SyncOptions,
+) -> Result<PathBuf, SyncError> {
+    let source_path = &source_sub.path;
+    let (Some(dest_parent), Some(dest_stem)) = (dest_file.parent(), dest_file.file_stem()) else {
+        return Err(SyncError::InvalidPath(dest_file.to_path_buf()));
+    };
+    // Media outside the mirrored directory, such as in a manifest, is placed at the top
+    let dest_parent = match &options.sub_out {
+        Some(sub_out) => sub_out.join(
+            options
+                .mirrored
+                .as_ref()
+                .and_then(|root| dest_parent.strip_prefix(root).ok())
+                .unwrap_or(Path::new("")),
+        ),
+        None => dest_parent.to_path_buf(),
+    };
+    let extension = match (&options.out_ext, source_path.extension()) {
+        (Some(out_ext), _) => out_ext.clone(),
+        (None, Some(extension)) => extension.to_string_lossy().to_string(),
+        (None, None) => return Err(SyncError::InvalidPath(source_path.to_path_buf())),
+    };
+    let first_target = match &options.template {
+        Some(template) => {
+            // The suffix holds a language followed by any tags, but tags come from the name so
+            // they're available even when only a language is placed
+            let lang = suffix
+                .as_deref()
+                .and_then(|s| s.split('.').find(|part| lang::tags(part).is_empty()));
+            let tags = lang::tags(&source_sub.name).join(".");
+            template
+                .render(
+                    &dest_parent,
+                    &template::Fields {
+                        stem: &dest_stem.to_string_lossy(),
+                        lang,
+                        ext: &extension,
+                        tag: Some(&tags),
+                    },
+                )
+                .ok_or_else(|| SyncError::InvalidPath(dest_file.to_path_buf()))?
+        }
+        None => dest_parent.join(format!(
+            "{}{}.{extension}",
+            dest_stem.to_string_lossy(),
+            suffix.map(|s| format!(".{s}")).unwrap_or_default()
+        )),
+    };
+    let mut target_name = first_target.clone();
+    let mut disambiguator = 2;
+    while used_targets.contains(&target_name) {
+        let mut numbered = first_target.file_stem().unwrap_or_default().to_os_string();
+        numbered.push(format!(".{disambiguator}"));
+        if let Some(extension) = first_target.extension() {
+            numbered.push(".");
+            numbered.push(extension);
+        }
+        target_name = first_target.with_file_name(numbered);
+        disambiguator += 1;
+    }
+    used_targets.insert(target_name.clone());
+    Ok(target_name)
+}
+
+/// Reads the cues of an SRT or WebVTT subtitle in any detected encoding, warning about any
+/// WebVTT settings and styling that had to be dropped
+fn read_cues(path: &Path) -> Result<Vec<srt::Cue>, SyncError> {
+    let bytes = read(path).map_err(SyncError::io(path))?;
+    let content = encoding::to_utf8(&bytes).ok_or_else(|| {
+        SyncError::io(path)(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "unknown text encoding",
+        ))
+    })?;
+    if !has_extension(path, &["vtt".to_string()]) {
+        return srt::parse_srt(content.text()).map_err(|source| SyncError::Srt {
+            path: path.to_path_buf(),
+            source,
+        });
+    }
+
+    let (cues, dropped) = vtt::parse_vtt(content.text()).map_err(|source| SyncError::Vtt {
+        path: path.to_path_buf(),
+        source,
+    })?;
+    if dropped > 0 {
+        warn!(
             "{}",
-            format!("Replacing file {}", target_name.to_string_lossy()).red()
+            format!(
+                "Dropped {dropped} cue settings, tags, or style blocks from {}",
+                path.to_string_lossy()
+            )
+            .yellow()
         );
-        remove_file(&target_name)?;
     }
+    Ok(cues)
+}
 
-    if copy_sub {
-        copy(source_sub.path(), target_name)?;
+/// Moves the existing `target` out of the way of its replacement, backing it up with --backup.
+/// Without a backup it's removed, unless the replacement is `renamed_over` it
+fn set_aside(
+    target: &Path,
+    renamed_over: bool,
+    options: &SyncOptions,
+) -> Result<Option<PathBuf>, SyncError> {
+    let backup = if options.backup {
+        let backup = fs_ops::backup_path(target);
+        info!(
+            "{}",
+            format!(
+                "Backing up {} to {}",
+                target.to_string_lossy(),
+                backup.to_string_lossy()
+            )
+            .yellow()
+        );
+        rename(target, &backup).map_err(SyncError::io(target))?;
+        Some(backup)
     } else {
-        symlink(source_sub.path(), target_name)?;
+        info!(
+            "{}",
+            format!("Replacing file {}", target.to_string_lossy()).red()
+        );
+        if !renamed_over {
+            remove_file(target).map_err(SyncError::io(target))?;
+        }
+        None
+    };
+    if let Some(journal) = &options.journal {
+        journal.record(&journal::Entry::Removed {
+            target: target.to_path_buf(),
+            backup: backup.clone(),
+        })?;
     }
+    Ok(backup)
+}
 
-    Ok(())
+/// Writes `target` with `write` through a temporary file beside it, then renames it into place so
+/// a failed or interrupted write never leaves a partial target. A copy of `copied` is first
+/// compared with it under --verify and given its modification time under --preserve-mtime,
+/// unless `write` returns that it hardlinked an earlier copy whose time that would change. An
+/// existing target being `replaced` is only set aside once the new one is complete. Returns
+/// where it was backed up to
+fn write_into_place(
+    write: impl FnOnce(&Path) -> Result<bool, SyncError>,
+    target: &Path,
+    copied: Option<&Path>,
+    replaced: bool,
+    options: &SyncOptions,
+) -> Result<Option<PathBuf>, SyncError> {
+    let temp = fs_ops::temp_path(target);
+    // A write cut short by Ctrl-C is removed rather than left half-written
+    let writing = interrupt::writing(&temp);
+    let result = write(&temp).and_then(|linked| {
+        if let Some(source) = copied.filter(|_| options.verify) {
+            if fs_ops::sha256(source).map_err(SyncError::io(source))?
+                != fs_ops::sha256(&temp).map_err(SyncError::io(target))?
+            {
+                return Err(SyncError::Corrupted(target.to_path_buf()));
+            }
+        }
+        if let Some(source) = copied.filter(|_| options.preserve_mtime && !linked) {
+            let modified = metadata(source)
+                .and_then(|m| m.modified())
+                .map_err(SyncError::io(source))?;
+            File::options()
+                .write(true)
+                .open(&temp)
+                .and_then(|file| file.set_modified(modified))
+                .map_err(SyncError::io(target))?;
+        }
+        let backup = if replaced {
+            set_aside(target, true, options)?
+        } else {
+            None
+        };
+        rename(&temp, target).map_err(SyncError::io(target))?;
+        Ok(backup)
+    });
+    if result.is_err() {
+        let _ = remove_file(&temp);
+    }
+    drop(writing);
+    result
+}
+
+/// Finds an earlier copy with the same contents as `source` for --dedup, otherwise recording
+/// `target` as the copy of those contents
+fn earlier_copy(
+    source: &Path,
+    target: &Path,
+    options: &SyncOptions,
+) -> Result<Option<PathBuf>, SyncError> {
+    let checksum = fs_ops::sha256(source).map_err(SyncError::io(source))?;
+    let mut copies = options.copies.lock().expect("copies lock");
+    match copies.get(&checksum) {
+        Some(original) if original.is_file() => Ok(Some(original.clone())),
+        _ => {
+            copies.insert(checksum, target.to_path_buf());
+            Ok(None)
+        }
+    }
+}
+
+/// Checks that subtitles can be placed in `dir` before anything is changed, returning whether it
+/// is missing and has to be created, which is only allowed with `create`
+fn check_target_parent(dir: &Path, create: bool) -> Result<bool, SyncError> {
+    let checked = if dir.as_os_str().is_empty() {
+        Path::new(".")
+    } else {
+        dir
+    };
+    match metadata(checked) {
+        Ok(metadata) if !metadata.is_dir() => {
+            Err(SyncError::io(dir)(io::ErrorKind::NotADirectory.into()))
+        }
+        Ok(metadata) if metadata.permissions().readonly() => {
+            Err(SyncError::ReadOnlyDir(dir.to_path_buf()))
+        }
+        // Permission bits don't say whether this user may write, so a file is created to see
+        Ok(_) => match tempfile::Builder::new()
+            .prefix(".subsync-")
+            .tempfile_in(checked)
+        {
+            Ok(_) => Ok(false),
+            Err(e)
+                if matches!(
+                    e.kind(),
+                    io::ErrorKind::PermissionDenied | io::ErrorKind::ReadOnlyFilesystem
+                ) =>
+            {
+                Err(SyncError::ReadOnlyDir(dir.to_path_buf()))
+            }
+            Err(e) => Err(SyncError::io(dir)(e)),
+        },
+        Err(e) if e.kind() == io::ErrorKind::NotFound && create => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            Err(SyncError::MissingDir(dir.to_path_buf()))
+        }
+        Err(e) => Err(SyncError::io(dir)(e)),
+    }
+}
+
+/// Links or copies the subtitle of `placement` to its target
+fn place_subtitle(placement: &Placement, options: &SyncOptions) -> Result<SyncOutcome, SyncError> {
+    let source_sub = &placement.source;
+    let source_path = &placement.linked;
+    let size = if *source_path == source_sub.path {
+        source_sub.size
+    } else {
+        metadata(source_path).map_or(0, |m| m.len())
+    };
+    let target_name = placement.target.clone();
+    let target_parent = target_name.parent().unwrap_or(Path::new("."));
+    // An outcome placing the subtitle at `target` without falling back, deduplicating, or backing
+    // anything up
+    let outcome = |target: PathBuf, operation: &'static str, status: Status| SyncOutcome {
+        source: source_path.clone(),
+        size,
+        target,
+        operation,
+        status,
+        fell_back: false,
+        deduplicated: false,
+        selected_by: placement.selected_by.clone(),
+        backup: None,
+    };
+    // Extracted subtitles are removed once the run ends, so links to them would break
+    let link_mode = if options.staging.contains(source_path) {
+        LinkMode::Copy
+    } else {
+        options.link_mode
+    };
+
+    let source_extension = source_path
+        .extension()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_lowercase();
+    let converted = options.convert
+        && options
+            .out_ext
+            .as_deref()
+            .is_some_and(|out_ext| converts(&source_extension, out_ext));
+
+    // A sidecar also recognizes targets whose contents were rewritten from the source
+    let recorded = options
+        .sidecar
+        .then(|| sidecar::Sidecar::read(&target_name))
+        .flatten()
+        .filter(|_| symlink_metadata(&target_name).is_ok());
+    if options.skip_existing
+        && match recorded {
+            Some(recorded) => recorded.matches(source_path, size),
+            None => fs_ops::up_to_date(
+                source_path,
+                &target_name,
+                link_mode == LinkMode::Symlink
+                    && options.shift.is_none()
+                    && placement.appended.is_none()
+                    && !converted,
+            ),
+        }
+    {
+        verbose!("Unchanged {}", target_name.to_string_lossy());
+        return Ok(outcome(target_name, link_mode.verb(), Status::Unchanged));
+    }
+
+    if fs_ops::same_file(source_path, &target_name) {
+        warn!(
+            "{}",
+            format!(
+                "Skipping {}, it is the same file as {}",
+                source_path.to_string_lossy(),
+                target_name.to_string_lossy()
+            )
+            .yellow()
+        );
+        return Ok(outcome(target_name, link_mode.verb(), Status::Skipped));
+    }
+
+    if let Some(embedded) = &options.embedded {
+        if embedded.found(&placement.media) && embedded.skip {
+            return Ok(outcome(target_name, link_mode.verb(), Status::Skipped));
+        }
+    }
+
+    let appended = placement.appended.as_ref().map(|a| &a.path);
+    let rewritten = options.shift.is_some() || appended.is_some() || converted;
+    if rewritten {
+        let timed = TIMED_EXTENSIONS.map(String::from);
+        if let Some(path) = [Some(source_path), appended]
+            .into_iter()
+            .flatten()
+            .find(|path| !has_extension(path, &timed))
+        {
+            return Err(SyncError::UnsupportedTiming(path.clone()));
+        }
+    }
+
+    let link_source = if options.relative && link_mode == LinkMode::Symlink {
+        fs_ops::relative_path(target_parent, source_path)
+            .map_err(SyncError::io(source_path))?
+            .unwrap_or_else(|| source_path.clone())
+    } else {
+        source_path.clone()
+    };
+
+    if let Some(severity) = options.validate {
+        if has_extension(source_path, &["srt".to_string()]) {
+            if let Err(issues) = srt::validate(source_path) {
+                match severity {
+                    Severity::Error => {
+                        return Err(SyncError::Invalid {
+                            path: source_path.clone(),
+                            issues,
+                        })
+                    }
+                    Severity::Warn => {
+                        info!(
+                            "{}",
+                            format!("{} has problems:", source_path.to_string_lossy()).yellow()
+                        );
+                        for issue in issues {
+                            info!("{}", format!(" - {issue}").yellow());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Dangling symlinks, such as ones left after the subtitle library moved, occupy the target
+    // too
+    let occupied = symlink_metadata(&target_name).is_ok();
+    if options.skip_existing && !options.overwrite && occupied {
+        info!(
+            "{}",
+            format!(
+                "Skipping {}, it differs from {}. Use --overwrite to replace it",
+                target_name.to_string_lossy(),
+                source_path.to_string_lossy()
+            )
+            .yellow()
+        );
+        return Ok(outcome(target_name, link_mode.verb(), Status::Skipped));
+    }
+
+    // Copies and writes would silently replace the target, unlike links
+    if !options.overwrite && occupied {
+        return Err(SyncError::io(&target_name)(
+            io::ErrorKind::AlreadyExists.into(),
+        ));
+    }
+
+    // Templates and --sub-out lay out folders of their own, so those are always created
+    let creates_parent = options.mkdir || options.template.is_some() || options.sub_out.is_some();
+    let created = check_target_parent(target_parent, creates_parent)?;
+    if created && options.dry_run {
+        info!("Would create {}", target_parent.to_string_lossy());
+    }
+
+    let overwritten = occupied && options.overwrite;
+    let status = if overwritten {
+        Status::Replaced
+    } else {
+        Status::Created
+    };
+    let mut fell_back = false;
+    let mut deduplicated = false;
+    let mut operation = match (options.shift, appended) {
+        (_, Some(_)) => "merge",
+        (Some(_), None) => "shift",
+        (None, None) if converted => "convert",
+        (None, None) => link_mode.verb(),
+    };
+
+    if options.dry_run {
+        if overwritten && options.backup {
+            info!(
+                "Would back up {} to {}",
+                target_name.to_string_lossy(),
+                fs_ops::backup_path(&target_name).to_string_lossy()
+            );
+        } else if overwritten {
+            info!("Would replace {}", target_name.to_string_lossy());
+        }
+        let (description, source) = match (options.shift, appended) {
+            (_, Some(appended)) => (
+                format!("merge {} with", appended.to_string_lossy()),
+                source_path,
+            ),
+            (Some(shift), None) => (format!("shift by {shift} ms"), source_path),
+            (None, None) if converted => (operation.to_string(), source_path),
+            (None, None) => (operation.to_string(), &link_source),
+        };
+        info!(
+            "Would {} {} -> {}",
+            description,
+            source.to_string_lossy(),
+            target_name.to_string_lossy()
+        );
+        return Ok(SyncOutcome {
+            backup: (overwritten && options.backup).then(|| fs_ops::backup_path(&target_name)),
+            ..outcome(target_name, operation, status)
+        });
+    }
+
+    if overwritten && !options.yes {
+        let confirmed = if options.non_interactive {
+            info!(
+                "{}",
+                format!(
+                    "Not replacing {} without --yes",
+                    target_name.to_string_lossy()
+                )
+                .yellow()
+            );
+            false
+        } else {
+            let existing_size = metadata(&target_name).map(|m| m.len()).unwrap_or(0);
+            output::prompt(
+                &format!(
+                    "Replace {} ({}) with {}?",
+                    target_name.to_string_lossy(),
+                    humansize::format_size(existing_size, DECIMAL),
+                    source_path
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                ),
+                false,
+                |message| Confirm::new(message).with_default(false).prompt(),
+            )?
+        };
+        if !confirmed {
+            return Ok(outcome(target_name, operation, Status::Skipped));
+        }
+    }
+
+    // Copies and rewrites replace an existing target only once they're complete
+    let written = rewritten || link_mode == LinkMode::Copy;
+    let mut backup = None;
+    if overwritten && !written {
+        backup = set_aside(&target_name, false, options)?;
+    }
+
+    if created {
+        create_dir_all(target_parent).map_err(SyncError::io(target_parent))?;
+    }
+
+    if rewritten {
+        backup = write_into_place(
+            |temp| {
+                let mut cues = read_cues(source_path)?;
+                if let Some(appended) = appended {
+                    srt::append_srt(&mut cues, read_cues(appended)?);
+                }
+                if let Some(shift) = options.shift {
+                    srt::shift_srt(&mut cues, shift);
+                }
+                let content = if has_extension(&target_name, &["vtt".to_string()]) {
+                    vtt::write_vtt(&cues)
+                } else {
+                    srt::write_srt(&cues)
+                };
+                write(temp, content)
+                    .map(|()| false)
+                    .map_err(SyncError::io(&target_name))
+            },
+            &target_name,
+            None,
+            overwritten,
+            options,
+        )?;
+    } else {
+        match link_mode {
+            LinkMode::Copy => {
+                let text = options.transcode || (options.eol.is_some() && is_text(source_path));
+                let original = if options.dedup && !text {
+                    earlier_copy(source_path, &target_name, options)?
+                } else {
+                    None
+                };
+                backup = write_into_place(
+                    |temp| {
+                        if let Some(original) = original {
+                            match hard_link(&original, temp) {
+                                Ok(()) => {
+                                    verbose!(
+                                        "{} has the contents of {}, hardlinking it",
+                                        source_path.to_string_lossy(),
+                                        original.to_string_lossy()
+                                    );
+                                    deduplicated = true;
+                                    return Ok(true);
+                                }
+                                Err(e) => verbose!(
+                                    "Can't hardlink {} to {} ({e}), copying instead",
+                                    target_name.to_string_lossy(),
+                                    original.to_string_lossy()
+                                ),
+                            }
+                        }
+                        if text {
+                            text_copy(source_path, temp, options.transcode, options.eol)
+                                .map(|()| false)
+                        } else {
+                            copy(source_path, temp)
+                                .map(|_| false)
+                                .map_err(SyncError::io(&target_name))
+                        }
+                    },
+                    &target_name,
+                    Some(source_path),
+                    overwritten,
+                    options,
+                )?;
+            }
+            LinkMode::Hardlink => {
+                hard_link(source_path, &target_name).map_err(|e| {
+                    if e.kind() == io::ErrorKind::CrossesDevices {
+                        SyncError::CrossDevice(source_path.clone())
+                    } else {
+                        SyncError::io(&target_name)(e)
+                    }
+                })?;
+            }
+            LinkMode::Symlink => {
+                if let Err(e) = fs_ops::link_file(&link_source, &target_name) {
+                    if options.no_fallback || !fs_ops::is_unsupported_link(&e) {
+                        return Err(SyncError::io(&target_name)(e));
+                    }
+                    info!(
+                        "{}",
+                        format!(
+                            "Can't symlink {} ({e}), copying instead",
+                            target_name.to_string_lossy()
+                        )
+                        .yellow()
+                    );
+                    write_into_place(
+                        |temp| {
+                            copy(source_path, temp)
+                                .map(|_| false)
+                                .map_err(SyncError::io(&target_name))
+                        },
+                        &target_name,
+                        Some(source_path),
+                        false,
+                        options,
+                    )?;
+                    operation = LinkMode::Copy.verb();
+                    fell_back = true;
+                }
+            }
+        }
+    }
+    if deduplicated {
+        operation = LinkMode::Hardlink.verb();
+    }
+    let verified = options.verify && options.shift.is_none() && operation == "copy";
+
+    if let Some(journal) = &options.journal {
+        journal.record(&journal::Entry::Created {
+            target: target_name.clone(),
+        })?;
+    }
+    if options.sidecar {
+        let path =
+            sidecar::Sidecar::new(source_path, size, placement.selected_by.clone(), operation)
+                .write(&target_name)?;
+        if let Some(journal) = &options.journal {
+            journal.record(&journal::Entry::Created { target: path })?;
+        }
+    }
+    verbose!(
+        "{} {} -> {}{}",
+        operation,
+        source_path.to_string_lossy(),
+        target_name.to_string_lossy(),
+        if verified { " (verified)" } else { "" }
+    );
+
+    Ok(SyncOutcome {
+        fell_back,
+        deduplicated,
+        backup,
+        ..outcome(target_name, operation, status)
+    })
 }
@@ -0,0 +1,94 @@
+use std::{
+    collections::HashMap,
+    fs::read_to_string,
+    path::{Path, PathBuf},
+};
+
+use serde::Deserialize;
+
+use crate::{
+    error::SyncError, media_stem, pair_candidate, plan_candidates, plan_folder, FolderResult,
+    Limit, SyncOptions, Units,
+};
+
+/// Explicit pairings of subtitles with media files read with `--manifest`, such as
+///
+/// ```toml
+/// media-dir = "Season 1"
+///
+/// [[entry]]
+/// subtitles = "subs/Special"
+/// media = "Season 1/Show.S00E01.mkv"
+/// ```
+///
+/// Relative paths are resolved against the manifest's folder
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Manifest {
+    /// Folder whose media missing from the entries is reported as unmatched
+    pub media_dir: Option<PathBuf>,
+    #[serde(rename = "entry", default)]
+    pub entries: Vec<Entry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Entry {
+    /// Folder of subtitles to select from, or a single subtitle file
+    pub subtitles: PathBuf,
+    /// Media file the subtitles are placed next to
+    pub media: PathBuf,
+}
+
+impl Manifest {
+    pub fn load(path: &Path) -> Result<Self, SyncError> {
+        let content = read_to_string(path).map_err(SyncError::io(path))?;
+        let mut manifest: Self = toml::from_str(&content).map_err(|e| SyncError::Manifest {
+            path: path.to_path_buf(),
+            message: e.message().to_string(),
+        })?;
+        if manifest.entries.is_empty() {
+            return Err(SyncError::Manifest {
+                path: path.to_path_buf(),
+                message: "no [[entry]] tables".to_string(),
+            });
+        }
+
+        let base = path.parent().unwrap_or(Path::new(""));
+        manifest.media_dir = manifest.media_dir.map(|dir| base.join(dir));
+        for entry in &mut manifest.entries {
+            entry.subtitles = base.join(&entry.subtitles);
+            entry.media = base.join(&entry.media);
+        }
+        Ok(manifest)
+    }
+
+    /// Synchronizes each entry, selecting among the subtitles of entries naming a folder. The
+    /// stems of the entries are removed from `destination_stems`
+    pub fn sync(
+        &self,
+        destination_stems: &mut HashMap<String, Vec<PathBuf>>,
+        options: &SyncOptions,
+        limit: &mut Limit,
+    ) -> Result<Vec<FolderResult>, SyncError> {
+        let mut units = Units::new(self.entries.len(), destination_stems, options, limit);
+        for entry in &self.entries {
+            let stem = media_stem(&entry.media)?;
+            // Media an earlier entry already placed subtitles for isn't taken again
+            let Some(media_files) = units.take(&stem) else {
+                continue;
+            };
+            let media = std::slice::from_ref(&entry.media);
+            let plan = if entry.subtitles.is_dir() {
+                plan_folder(&entry.subtitles, media, options)
+            } else {
+                let dir = entry.subtitles.parent().unwrap_or(Path::new("."));
+                pair_candidate(&entry.subtitles).and_then(|candidate| {
+                    plan_candidates(dir, vec![candidate], media, options.lang.clone(), options)
+                })
+            };
+            units.run(stem, media_files, plan)?;
+        }
+        Ok(units.finish())
+    }
+}
@@ -0,0 +1,394 @@
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    fmt::Display,
+    iter::Peekable,
+    str::Chars,
+    sync::LazyLock,
+};
+
+use regex::Regex;
+
+use crate::{Metric, Normalize};
+
+/// Explicit season and episode markers such as `S01E02` and `1x02`
+static MARKED_EPISODE: LazyLock<[Regex; 2]> = LazyLock::new(|| {
+    [
+        Regex::new(r"(?i)\bs(\d{1,2})[ ._-]*e(\d{1,3})").expect("valid regex"),
+        Regex::new(r"(?i)\b(\d{1,2})x(\d{1,3})\b").expect("valid regex"),
+    ]
+});
+
+/// Another episode number right after a marked one, such as the `E02` of `S01E01E02` or the
+/// `-E02` of `S01E01-E02`
+static EXTRA_EPISODE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^[ ._-]*e(\d{1,3})").expect("valid regex"));
+
+/// A lone episode number such as `E05` or `Episode 5`
+static BARE_EPISODE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)(?:\be|\b)(\d{1,3})\b").expect("valid regex"));
+
+/// A season folder name such as `Season 01`, `Series 2`, or `S03`
+static SEASON: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(?:season|series|s)?[ ._-]*(\d{1,3})$").expect("valid regex")
+});
+
+/// A specials folder name such as `Specials`, which holds season 0
+static SPECIALS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?i)^specials?$").expect("valid regex"));
+
+/// A run of digits, such as an episode number
+static NUMBER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\d+").expect("valid regex"));
+
+/// A file name with every number replaced by a wildcard, such as `Show.S*E*.English.srt`,
+/// learned from a manually selected subtitle
+#[derive(Debug)]
+pub struct NamePattern {
+    regex: Regex,
+    display: String,
+}
+
+impl NamePattern {
+    pub fn learn(name: &str) -> Self {
+        let mut pattern = String::from("^");
+        let mut last = 0;
+        for number in NUMBER.find_iter(name) {
+            pattern.push_str(&regex::escape(&name[last..number.start()]));
+            pattern.push_str(r"\d+");
+            last = number.end();
+        }
+        pattern.push_str(&regex::escape(&name[last..]));
+        pattern.push('$');
+
+        Self {
+            regex: Regex::new(&pattern).expect("escaped pattern to be valid"),
+            display: NUMBER.replace_all(name, "*").to_string(),
+        }
+    }
+
+    pub fn matches(&self, name: &str) -> bool {
+        self.regex.is_match(name)
+    }
+}
+
+impl Display for NamePattern {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.display)
+    }
+}
+
+/// Lowercases a name and collapses every run of separators into a single space
+pub fn normalize(name: &str) -> String {
+    name.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Accented Latin letters and the letters they're sorted with by `natural_cmp`
+const ACCENTED: &str = "àáâãäåçèéêëìíîïñòóôõöøùúûüýÿ";
+const UNACCENTED: &str = "aaaaaaceeeeiiiinoooooouuuuyy";
+
+/// Lowercases a letter and removes its accent
+fn fold(c: char) -> char {
+    let c = c.to_lowercase().next().unwrap_or(c);
+    ACCENTED
+        .chars()
+        .position(|accented| accented == c)
+        .and_then(|i| UNACCENTED.chars().nth(i))
+        .unwrap_or(c)
+}
+
+/// Takes the run of digits at the front of `chars`, without leading zeros
+fn take_number(chars: &mut Peekable<Chars>) -> String {
+    let mut number = String::new();
+    while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+        if !(number.is_empty() && digit == '0') {
+            number.push(digit);
+        }
+    }
+    number
+}
+
+/// Orders names the way people read them: runs of digits by their value, so `ep2` comes before
+/// `ep10`, and letters regardless of case and accents. Names that only differ in those are
+/// ordered bytewise
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let (mut a_chars, mut b_chars) = (a.chars().peekable(), b.chars().peekable());
+    loop {
+        let order = match (a_chars.peek(), b_chars.peek()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => Ordering::Less,
+            (Some(_), None) => Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let (x, y) = (take_number(&mut a_chars), take_number(&mut b_chars));
+                x.len().cmp(&y.len()).then_with(|| x.cmp(&y))
+            }
+            (Some(&x), Some(&y)) => {
+                a_chars.next();
+                b_chars.next();
+                fold(x).cmp(&fold(y))
+            }
+        };
+        if order.is_ne() {
+            return order;
+        }
+    }
+}
+
+/// How names are compared when pairing folders and files with media stems
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MatchRules {
+    pub ignore_case: bool,
+    pub normalize: Option<Normalize>,
+}
+
+impl MatchRules {
+    /// Whether names are compared as they are
+    fn is_exact(&self) -> bool {
+        !self.ignore_case && self.normalize.is_none()
+    }
+
+    /// The form of a name that is compared under these rules
+    pub fn key(&self, name: &str) -> String {
+        match self.normalize {
+            Some(Normalize::Separators) => normalize(name),
+            Some(Normalize::Titles) => {
+                let mut normalized = normalize(name);
+                if let Some(end) = episode_end(&normalized) {
+                    normalized.truncate(end);
+                }
+                normalized
+            }
+            None if self.ignore_case => name.to_lowercase(),
+            None => name.to_string(),
+        }
+    }
+
+    /// Whether the key of a longer name, such as a subtitle file name, starts with `stem_key`
+    pub fn key_starts_with(&self, key: &str, stem_key: &str) -> bool {
+        key.strip_prefix(stem_key)
+            .is_some_and(|rest| match self.normalize {
+                Some(_) => rest.is_empty() || rest.starts_with(' '),
+                None => rest.starts_with('.'),
+            })
+    }
+}
+
+/// Finds where the episode number of a name ends, so anything after it such as an episode
+/// title can be cut off
+fn episode_end(name: &str) -> Option<usize> {
+    MARKED_EPISODE
+        .iter()
+        .find_map(|re| re.find(name))
+        .or_else(|| BARE_EPISODE.find_iter(name).last())
+        .map(|m| m.end())
+}
+
+/// Finds the stem a folder named `name` belongs to: the stem equal to it, or else the only stem
+/// with the same key under `rules`, or else the only stem containing it as a run of whole
+/// dot-separated parts, such as `Show.S01E01.1080p` for `S01E01`
+pub fn find_stem<'a, V>(
+    name: &str,
+    stems: &'a HashMap<String, V>,
+    rules: MatchRules,
+) -> Option<&'a String> {
+    if let Some((stem, _)) = stems.get_key_value(name) {
+        return Some(stem);
+    }
+    let key = rules.key(name);
+    if !rules.is_exact() {
+        if let Some(stem) = only(stems.keys().filter(|stem| rules.key(stem) == key)) {
+            return Some(stem);
+        }
+    }
+
+    // Normalized keys separate their parts with spaces instead of dots
+    let separator = if rules.normalize.is_some() { ' ' } else { '.' };
+    let parts: Vec<_> = key.split(separator).collect();
+    only(stems.keys().filter(|stem| {
+        let stem_key = rules.key(stem);
+        let stem_parts: Vec<_> = stem_key.split(separator).collect();
+        stem_parts
+            .windows(parts.len())
+            .any(|window| window == parts)
+    }))
+}
+
+/// Whether `stem` contains `token` as a whole part delimited by anything but letters and
+/// digits, ignoring case. A number may also directly follow an `E` or `x`, so `01` is found in
+/// `Show.S01E01` and `Show.1x01` but not in `Show.2014` or after the `S` of `S01`
+pub fn contains_token(stem: &str, token: &str) -> bool {
+    let (stem, token) = (stem.to_lowercase(), token.to_lowercase());
+    if token.is_empty() {
+        return false;
+    }
+    let numeric = token.chars().all(|c| c.is_ascii_digit());
+    let delimiter = |c: Option<char>| c.is_none_or(|c| !c.is_alphanumeric());
+    stem.match_indices(&token).any(|(start, _)| {
+        let before = stem[..start].chars().next_back();
+        let after = stem[start + token.len()..].chars().next();
+        (delimiter(before) || (numeric && matches!(before, Some('e' | 'x'))))
+            && (delimiter(after) || (numeric && !after.is_some_and(|c| c.is_ascii_digit())))
+    })
+}
+
+/// The single item of `found`, or `None` if there are none or several
+fn only<T>(mut found: impl Iterator<Item = T>) -> Option<T> {
+    match (found.next(), found.next()) {
+        (Some(item), None) => Some(item),
+        _ => None,
+    }
+}
+
+/// Extracts an explicit `SxxExx` or `NxNN` season and episode marker
+fn marked_episode(name: &str) -> Option<(u32, u32)> {
+    MARKED_EPISODE.iter().find_map(|re| {
+        let captures = re.captures(name)?;
+        Some((captures[1].parse().ok()?, captures[2].parse().ok()?))
+    })
+}
+
+/// Extracts the `(season, episode)` numbers from a name.
+/// Names with only a bare episode number, such as `Episode 5`, are assumed to be in season 1
+/// and use the last such number present
+pub fn extract_episode(name: &str) -> Option<(u32, u32)> {
+    marked_episode(name).or_else(|| {
+        let captures = BARE_EPISODE.captures_iter(name).last()?;
+        Some((1, captures[1].parse().ok()?))
+    })
+}
+
+/// Extracts every `(season, episode)` of a name covering several episodes, such as both of
+/// `Show.S01E01E02` or `Show.S01E01-E02`, in the order they appear. Names with a single episode
+/// give just that one
+pub fn extract_episodes(name: &str) -> Vec<(u32, u32)> {
+    let Some(captures) = MARKED_EPISODE[0].captures(name) else {
+        return extract_episode(name).into_iter().collect();
+    };
+    let Some(season) = captures[1].parse().ok() else {
+        return Vec::new();
+    };
+    let mut episodes: Vec<_> = captures[2]
+        .parse()
+        .ok()
+        .map(|e| (season, e))
+        .into_iter()
+        .collect();
+    let mut end = captures.get(0).expect("whole match").end();
+    while let Some(extra) = EXTRA_EPISODE.captures(&name[end..]) {
+        if let Ok(episode) = extra[1].parse() {
+            episodes.push((season, episode));
+        }
+        end += extra.get(0).expect("whole match").end();
+    }
+    episodes
+}
+
+/// Extracts the season number from a season folder name. Specials are season 0
+pub fn extract_season(name: &str) -> Option<u32> {
+    let name = name.trim();
+    if SPECIALS.is_match(name) {
+        return Some(0);
+    }
+    SEASON.captures(name)?[1].parse().ok()
+}
+
+/// Whether a season folder holds specials, such as `Specials` or `Season 00`
+pub fn is_specials(name: &str) -> bool {
+    extract_season(name) == Some(0)
+}
+
+/// Scores how alike two names are from 0.0 to 1.0.
+/// Names that both carry an `SxxExx` marker are compared by that marker alone,
+/// otherwise the character bigrams of the normalized names are compared
+pub fn similarity(a: &str, b: &str) -> f64 {
+    if let (Some(a), Some(b)) = (marked_episode(a), marked_episode(b)) {
+        return if a == b { 1.0 } else { 0.0 };
+    }
+
+    let (a, b) = (normalize(a), normalize(b));
+    let bigrams = |s: &str| -> HashSet<(char, char)> {
+        let chars: Vec<_> = s.chars().filter(|c| !c.is_whitespace()).collect();
+        chars.windows(2).map(|w| (w[0], w[1])).collect()
+    };
+    let (a, b) = (bigrams(&a), bigrams(&b));
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+
+    2.0 * a.intersection(&b).count() as f64 / (a.len() + b.len()) as f64
+}
+
+/// The title of a name for --similarity: its normalized words without years such as `1999`
+fn title(name: &str) -> String {
+    normalize(name)
+        .split(' ')
+        .filter(|word| {
+            !(word.len() == 4
+                && (word.starts_with("19") || word.starts_with("20"))
+                && word.chars().all(|c| c.is_ascii_digit()))
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Scores how alike two names are from 0.0 to 1.0 by `metric`. The string distances compare
+/// titles, while [`Metric::Bigram`] is [`similarity`]
+pub fn score(a: &str, b: &str, metric: Metric) -> f64 {
+    match metric {
+        Metric::JaroWinkler => strsim::jaro_winkler(&title(a), &title(b)),
+        Metric::Levenshtein => strsim::normalized_levenshtein(&title(a), &title(b)),
+        Metric::Bigram => similarity(a, b),
+    }
+}
+
+/// Finds the words shared by some but not all of `names`, most common first, along with the
+/// number of names containing each. Numbers and single characters are ignored
+pub fn keyword_suggestions(names: &[String], limit: usize) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for name in names {
+        let words: HashSet<_> = normalize(name).split(' ').map(str::to_string).collect();
+        for word in words {
+            *counts.entry(word).or_default() += 1;
+        }
+    }
+
+    let mut suggestions: Vec<_> = counts
+        .into_iter()
+        .filter(|(word, count)| {
+            word.chars().count() > 1
+                && !word.chars().all(|c| c.is_ascii_digit())
+                && *count > 1
+                && *count < names.len()
+        })
+        .collect();
+    suggestions.sort_unstable_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    suggestions.truncate(limit);
+    suggestions
+}
+
+/// Finds the candidates scoring highest against `name` by `metric` at or above `threshold`.
+/// More than one result means the best candidates tied
+pub fn best_matches<'a>(
+    name: &str,
+    candidates: impl IntoIterator<Item = &'a String>,
+    threshold: f64,
+    metric: Metric,
+) -> Vec<&'a String> {
+    let mut best = Vec::new();
+    let mut best_score = threshold;
+    for candidate in candidates {
+        let score = score(name, candidate, metric);
+        if score > best_score {
+            best_score = score;
+            best.clear();
+            best.push(candidate);
+        } else if score == best_score {
+            best.push(candidate);
+        }
+    }
+    best.sort_unstable();
+    best
+}
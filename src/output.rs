@@ -0,0 +1,208 @@
+use std::{
+    io::{self, IsTerminal, Write},
+    sync::{
+        atomic::{AtomicBool, AtomicU8, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+use colored::Colorize;
+use crossterm::{cursor, event, terminal, ExecutableCommand};
+use indicatif::{ProgressBar, ProgressStyle};
+use inquire::InquireError;
+
+/// How much is printed while running. Errors and the final result line are always printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    /// Only the final result line, such as `Done!`
+    ResultOnly,
+    /// Also the rest of the final summary
+    Quiet,
+    Normal,
+    Verbose,
+    Debug,
+}
+
+static LEVEL: AtomicU8 = AtomicU8::new(Level::Normal as u8);
+
+pub fn set_level(level: Level) {
+    LEVEL.store(level as u8, Ordering::Relaxed);
+}
+
+pub fn enabled(level: Level) -> bool {
+    LEVEL.load(Ordering::Relaxed) >= level as u8
+}
+
+/// Progress bar currently drawn, which output and prompts must hide while they run
+static PROGRESS: Mutex<Option<ProgressBar>> = Mutex::new(None);
+
+/// Runs `f` with any progress bar hidden so printed lines and prompts aren't drawn over
+pub fn suspend<R>(f: impl FnOnce() -> R) -> R {
+    let bar = PROGRESS.lock().expect("progress lock").clone();
+    match bar {
+        Some(bar) => bar.suspend(f),
+        None => f(),
+    }
+}
+
+/// How long prompts wait for a key press before using their default, if they give up at all
+static PROMPT_TIMEOUT: Mutex<Option<Duration>> = Mutex::new(None);
+
+/// Set once a prompt went unanswered, after which nobody is assumed to be at the terminal
+static UNATTENDED: AtomicBool = AtomicBool::new(false);
+
+pub fn set_prompt_timeout(timeout: Option<Duration>) {
+    *PROMPT_TIMEOUT.lock().expect("prompt timeout lock") = timeout;
+}
+
+/// Asks `message` by running the prompt `f` with any progress bar hidden. With a prompt timeout,
+/// the prompt only appears once a key is pressed, and `default` is used if none is pressed in
+/// time. Once a prompt has timed out, later prompts use their default straight away
+pub fn prompt<T>(
+    message: &str,
+    default: T,
+    f: impl FnOnce(&str) -> Result<T, InquireError>,
+) -> Result<T, InquireError> {
+    let timeout = *PROMPT_TIMEOUT.lock().expect("prompt timeout lock");
+    suspend(|| {
+        let Some(timeout) = timeout else {
+            return f(message);
+        };
+        if UNATTENDED.load(Ordering::Relaxed) {
+            eprintln!(
+                "{}",
+                format!("{message} Unanswered, using the default").dimmed()
+            );
+            return Ok(default);
+        }
+
+        eprintln!(
+            "{}",
+            format!(
+                "{message} Press a key within {}s to answer, or the default is used",
+                timeout.as_secs()
+            )
+            .dimmed()
+        );
+        if !key_pressed(timeout)? {
+            UNATTENDED.store(true, Ordering::Relaxed);
+            eprintln!("{}", "No answer, using the default".dimmed());
+            return Ok(default);
+        }
+        // The key is left queued, so it's the first the prompt receives
+        let mut stderr = io::stderr();
+        stderr.execute(cursor::MoveToPreviousLine(1))?;
+        stderr.execute(terminal::Clear(terminal::ClearType::CurrentLine))?;
+        stderr.flush()?;
+        f(message)
+    })
+}
+
+/// Waits up to `timeout` for a key press on the terminal without consuming it
+fn key_pressed(timeout: Duration) -> io::Result<bool> {
+    terminal::enable_raw_mode()?;
+    let pressed = event::poll(timeout);
+    terminal::disable_raw_mode()?;
+    pressed
+}
+
+/// How a status line is marked: done in green, replaced in yellow, and skipped in red
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mark {
+    Done,
+    Replaced,
+    Skipped,
+}
+
+/// Formats a status line such as `✔ S01E01  linked  english.srt (1.2 MB)`, padding the name and
+/// action to `widths` so the lines printed together align in columns
+pub fn status_line(
+    mark: Mark,
+    name: &str,
+    action: &str,
+    detail: &str,
+    widths: (usize, usize),
+) -> String {
+    let (name_width, action_width) = widths;
+    let icon = match mark {
+        Mark::Done => '✔',
+        Mark::Replaced => '⟳',
+        Mark::Skipped => '✗',
+    };
+    let line = format!("{icon} {name:<name_width$}  {action:<action_width$}  {detail}");
+    match mark {
+        Mark::Done => line.green(),
+        Mark::Replaced => line.yellow(),
+        Mark::Skipped => line.red(),
+    }
+    .to_string()
+}
+
+/// Counts finished folders on a progress bar, removing the bar when dropped
+pub struct Progress(Option<ProgressBar>);
+
+impl Progress {
+    /// Starts a bar out of `total` folders, unless output is quiet or stdout isn't a terminal
+    pub fn start(total: usize) -> Self {
+        if !enabled(Level::Normal) || !io::stdout().is_terminal() {
+            return Self(None);
+        }
+        let bar = ProgressBar::new(total as u64).with_style(
+            ProgressStyle::with_template("{bar:40} {pos}/{len} folders").expect("valid template"),
+        );
+        *PROGRESS.lock().expect("progress lock") = Some(bar.clone());
+        Self(Some(bar))
+    }
+
+    pub fn inc(&self) {
+        if let Some(bar) = &self.0 {
+            bar.inc(1);
+        }
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        if let Some(bar) = self.0.take() {
+            *PROGRESS.lock().expect("progress lock") = None;
+            bar.finish_and_clear();
+        }
+    }
+}
+
+/// Prints progress output, hidden by `--quiet`
+macro_rules! info {
+    ($($arg:tt)*) => {
+        if $crate::output::enabled($crate::output::Level::Normal) {
+            $crate::output::suspend(|| println!($($arg)*));
+        }
+    };
+}
+
+/// Prints a non-fatal problem to stderr, hidden by `--quiet`
+macro_rules! warn {
+    ($($arg:tt)*) => {
+        if $crate::output::enabled($crate::output::Level::Normal) {
+            $crate::output::suspend(|| eprintln!($($arg)*));
+        }
+    };
+}
+
+/// Prints details shown with `-v`
+macro_rules! verbose {
+    ($($arg:tt)*) => {
+        if $crate::output::enabled($crate::output::Level::Verbose) {
+            $crate::output::suspend(|| println!($($arg)*));
+        }
+    };
+}
+
+/// Prints details shown with `-vv`
+macro_rules! debug {
+    ($($arg:tt)*) => {
+        if $crate::output::enabled($crate::output::Level::Debug) {
+            $crate::output::suspend(|| println!($($arg)*));
+        }
+    };
+}
@@ -0,0 +1,92 @@
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use serde::Serialize;
+
+use crate::{error::SyncError, Mode, Status, SyncOutcome};
+
+/// Machine-readable summary of a run, written with `--report`. With `--dry-run` it lists the
+/// operations that would have been performed, in the same shape
+#[derive(Debug, Serialize)]
+pub struct Report {
+    mode: Mode,
+    /// Whether the operations were performed, false for dry runs
+    executed: bool,
+    matched: usize,
+    failed: usize,
+    unmatched: usize,
+    entries: Vec<Entry>,
+    unmatched_stems: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct Entry {
+    stem: String,
+    subtitle: Option<PathBuf>,
+    target: Option<PathBuf>,
+    operation: Option<&'static str>,
+    /// Strategy that selected the subtitle, including any it fell back to
+    strategy: Option<String>,
+    status: Option<Status>,
+    overwritten: bool,
+    /// Where the replaced file was moved with `--backup`
+    backup: Option<PathBuf>,
+    error: Option<String>,
+}
+
+impl Report {
+    pub fn new(
+        mode: Mode,
+        results: &[(String, Result<Vec<SyncOutcome>, SyncError>)],
+        unmatched_stems: Vec<String>,
+        executed: bool,
+    ) -> Self {
+        let mut entries = Vec::new();
+        for (stem, result) in results {
+            match result {
+                Ok(outcomes) => entries.extend(outcomes.iter().map(|outcome| Entry {
+                    stem: stem.clone(),
+                    subtitle: Some(outcome.source.clone()),
+                    target: Some(outcome.target.clone()),
+                    operation: Some(outcome.operation),
+                    strategy: outcome.selected_by.clone(),
+                    status: Some(outcome.status),
+                    overwritten: outcome.status == Status::Replaced,
+                    backup: outcome.backup.clone(),
+                    error: None,
+                })),
+                Err(e) => entries.push(Entry {
+                    stem: stem.clone(),
+                    subtitle: None,
+                    target: None,
+                    operation: None,
+                    strategy: None,
+                    status: None,
+                    overwritten: false,
+                    backup: None,
+                    error: Some(e.to_string()),
+                }),
+            }
+        }
+        let failed = results.iter().filter(|(_, r)| r.is_err()).count();
+
+        Self {
+            mode,
+            executed,
+            matched: results.len() - failed,
+            failed,
+            unmatched: unmatched_stems.len(),
+            entries,
+            unmatched_stems,
+        }
+    }
+
+    pub fn write(&self, path: &Path) -> Result<(), SyncError> {
+        let file = File::create(path).map_err(SyncError::io(path))?;
+        serde_json::to_writer_pretty(BufWriter::new(file), self)
+            .map_err(|e| SyncError::io(path)(e.into()))
+    }
+}
@@ -0,0 +1,84 @@
+use std::{
+    collections::HashSet,
+    fs::{read_to_string, remove_file, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::error::SyncError;
+
+/// Name of the progress file kept in the output directory with --resume, one finished media file
+/// per line
+pub const FILE_NAME: &str = ".subsync-resume";
+
+/// Media finished by earlier runs with --resume that were interrupted, and the file this run's
+/// progress is appended to so it can be resumed in turn
+#[derive(Debug)]
+pub struct Progress {
+    path: PathBuf,
+    done: HashSet<PathBuf>,
+    file: Mutex<Option<File>>,
+}
+
+impl Progress {
+    /// Reads the progress kept in `dir`, which is empty unless an earlier run was interrupted
+    pub fn load(dir: &Path) -> Result<Self, SyncError> {
+        let path = dir.join(FILE_NAME);
+        let done = match read_to_string(&path) {
+            Ok(content) => content
+                .lines()
+                .filter(|line| !line.is_empty())
+                .map(PathBuf::from)
+                .collect(),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => HashSet::new(),
+            Err(e) => return Err(SyncError::io(&path)(e)),
+        };
+        Ok(Self {
+            path,
+            done,
+            file: Mutex::new(None),
+        })
+    }
+
+    /// How many media files earlier runs finished
+    pub fn resumed(&self) -> usize {
+        self.done.len()
+    }
+
+    /// Whether an earlier run finished the media sharing a stem
+    pub fn is_done(&self, media_files: &[PathBuf]) -> bool {
+        media_files.iter().any(|file| self.done.contains(file))
+    }
+
+    /// Records the media sharing a stem as finished
+    pub fn complete(&self, media_files: &[PathBuf]) -> Result<(), SyncError> {
+        let mut file = self.file.lock().expect("progress lock");
+        let file = match &mut *file {
+            Some(file) => file,
+            None => file.insert(
+                OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(&self.path)
+                    .map_err(SyncError::io(&self.path))?,
+            ),
+        };
+        let mut lines = String::new();
+        for media in media_files {
+            lines.push_str(&media.to_string_lossy());
+            lines.push('\n');
+        }
+        file.write_all(lines.as_bytes())
+            .map_err(SyncError::io(&self.path))
+    }
+
+    /// Removes the progress once the run has gone through every folder
+    pub fn finish(&self) -> Result<(), SyncError> {
+        *self.file.lock().expect("progress lock") = None;
+        match remove_file(&self.path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(SyncError::io(&self.path)(e)),
+            _ => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use std::{fs::read_to_string, path::Path};
+
+use regex::Regex;
+use serde::{Deserialize, Deserializer};
+
+use crate::{error::SyncError, SubtitleSelectionStrategy};
+
+/// Selection settings for particular subtitle folders read with `--rules`, such as
+///
+/// ```toml
+/// [[rule]]
+/// folder = "*S01E0?*"
+/// keyword = "english"
+/// strategy = "size"
+/// ```
+///
+/// The first rule whose `folder` glob matches the name of a subtitle folder, ignoring case,
+/// replaces the keyword and strategy of the run for that folder. `*` matches any run of
+/// characters and `?` any single one
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rules {
+    #[serde(rename = "rule", default)]
+    rules: Vec<Rule>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    #[serde(deserialize_with = "glob")]
+    folder: Regex,
+    /// Text subtitle names must contain, lowercase. An empty keyword allows every subtitle
+    pub keyword: Option<String>,
+    pub strategy: Option<SubtitleSelectionStrategy>,
+}
+
+/// Reads a glob as an anchored, case-insensitive regex
+fn glob<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Regex, D::Error> {
+    let glob = String::deserialize(deserializer)?;
+    let mut pattern = String::from("(?i)^");
+    for c in glob.chars() {
+        match c {
+            '*' => pattern.push_str(".*"),
+            '?' => pattern.push('.'),
+            c => pattern.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    pattern.push('$');
+    Regex::new(&pattern).map_err(serde::de::Error::custom)
+}
+
+impl Rules {
+    pub fn load(path: &Path) -> Result<Self, SyncError> {
+        let invalid = |message: String| SyncError::Rules {
+            path: path.to_path_buf(),
+            message,
+        };
+        let content = read_to_string(path).map_err(SyncError::io(path))?;
+        let mut rules: Self =
+            toml::from_str(&content).map_err(|e| invalid(e.message().to_string()))?;
+        if rules.rules.is_empty() {
+            return Err(invalid("no [[rule]] tables".to_string()));
+        }
+        for rule in &mut rules.rules {
+            rule.keyword = rule.keyword.take().map(|k| k.to_lowercase());
+        }
+        Ok(rules)
+    }
+
+    /// The first rule matching the name of `folder`
+    pub fn find(&self, folder: &Path) -> Option<&Rule> {
+        let name = folder.file_name()?.to_string_lossy();
+        self.rules.iter().find(|rule| rule.folder.is_match(&name))
+    }
+
+    /// Whether any rule selects manually, which needs prompts
+    pub fn selects_manually(&self) -> bool {
+        self.rules
+            .iter()
+            .any(|rule| matches!(rule.strategy, Some(SubtitleSelectionStrategy::Manual)))
+    }
+}
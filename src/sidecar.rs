@@ -0,0 +1,72 @@
+use std::{
+    fs::{read_to_string, write},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::SyncError;
+
+/// Appended to a target's file name to name its sidecar, such as `Show.srt.subsync.json`
+pub const SUFFIX: &str = ".subsync.json";
+
+/// Provenance of a placed subtitle, written next to it with `--sidecar`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Sidecar {
+    /// Absolute path of the subtitle the target was placed from
+    pub source: PathBuf,
+    /// Size of the source in bytes when it was placed
+    pub size: u64,
+    /// Strategy that selected the source, absent when it was named explicitly with `--pairs`
+    pub strategy: Option<String>,
+    pub operation: String,
+    /// Seconds since the Unix epoch
+    pub created_at: u64,
+}
+
+/// The sidecar of `target`
+pub fn path(target: &Path) -> PathBuf {
+    let mut name = target.as_os_str().to_owned();
+    name.push(SUFFIX);
+    PathBuf::from(name)
+}
+
+/// The target a sidecar belongs to, or `None` if the path isn't a sidecar
+pub fn target(path: &Path) -> Option<PathBuf> {
+    let name = path.file_name()?.to_str()?;
+    let target = name.strip_suffix(SUFFIX).filter(|t| !t.is_empty())?;
+    Some(path.with_file_name(target))
+}
+
+impl Sidecar {
+    pub fn new(source: &Path, size: u64, strategy: Option<String>, operation: &str) -> Self {
+        Self {
+            source: std::path::absolute(source).unwrap_or_else(|_| source.to_path_buf()),
+            size,
+            strategy,
+            operation: operation.to_string(),
+            created_at: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+        }
+    }
+
+    /// Reads the sidecar of `target`, or `None` if it's missing or unreadable
+    pub fn read(target: &Path) -> Option<Self> {
+        serde_json::from_str(&read_to_string(path(target)).ok()?).ok()
+    }
+
+    /// Writes the sidecar next to `target`, returning its path
+    pub fn write(&self, target: &Path) -> Result<PathBuf, SyncError> {
+        let path = path(target);
+        let json = serde_json::to_string_pretty(self).expect("sidecar to serialize");
+        write(&path, json).map_err(SyncError::io(&path))?;
+        Ok(path)
+    }
+
+    /// Whether the target was placed from `source` while it had `size` bytes
+    pub fn matches(&self, source: &Path, size: u64) -> bool {
+        std::path::absolute(source).is_ok_and(|source| self.source == source) && self.size == size
+    }
+}
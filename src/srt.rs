@@ -0,0 +1,283 @@
+use std::{error::Error, fmt::Display, fs::read, path::Path};
+
+use crate::encoding;
+
+/// A point in time within a subtitle track, in milliseconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Timestamp(pub u64);
+
+impl Timestamp {
+    /// Parses an `HH:MM:SS,mmm` timestamp, also accepting `.` as the millisecond separator
+    fn parse(s: &str) -> Option<Self> {
+        let (hms, millis) = s.split_once([',', '.'])?;
+        let mut parts = hms.split(':');
+        let hours: u64 = parts.next()?.parse().ok()?;
+        let minutes: u64 = parts.next()?.parse().ok()?;
+        let seconds: u64 = parts.next()?.parse().ok()?;
+        if parts.next().is_some() || minutes >= 60 || seconds >= 60 || millis.len() != 3 {
+            return None;
+        }
+        let millis: u64 = millis.parse().ok()?;
+
+        Some(Self(
+            ((hours * 60 + minutes) * 60 + seconds) * 1000 + millis,
+        ))
+    }
+
+    /// Offsets the timestamp by `offset_ms`, clamping at zero
+    fn shifted(self, offset_ms: i64) -> Self {
+        Self(self.0.saturating_add_signed(offset_ms))
+    }
+}
+
+impl Display for Timestamp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:02}:{:02}:{:02},{:03}",
+            self.0 / 3_600_000,
+            self.0 / 60_000 % 60,
+            self.0 / 1000 % 60,
+            self.0 % 1000
+        )
+    }
+}
+
+/// A single subtitle entry
+#[derive(Debug, Clone)]
+pub struct Cue {
+    pub index: u32,
+    pub start: Timestamp,
+    pub end: Timestamp,
+    /// Lines of text displayed for this cue
+    pub text: Vec<String>,
+    /// Line number of the cue's index in the source file, starting at 1
+    pub line: usize,
+}
+
+#[derive(Debug)]
+pub enum SrtError {
+    InvalidIndex { line: usize, found: String },
+    InvalidTiming { line: usize, found: String },
+    MissingTiming { line: usize },
+}
+
+impl SrtError {
+    pub fn line(&self) -> usize {
+        match self {
+            Self::InvalidIndex { line, .. }
+            | Self::InvalidTiming { line, .. }
+            | Self::MissingTiming { line } => *line,
+        }
+    }
+
+    /// Describes the error without its line number
+    fn message(&self) -> String {
+        match self {
+            Self::InvalidIndex { found, .. } => format!("expected a cue index, found `{found}`"),
+            Self::InvalidTiming { found, .. } => {
+                format!("expected `HH:MM:SS,mmm --> HH:MM:SS,mmm`, found `{found}`")
+            }
+            Self::MissingTiming { .. } => "cue ends before its timing line".to_string(),
+        }
+    }
+}
+
+impl Display for SrtError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line(), self.message())
+    }
+}
+
+impl Error for SrtError {}
+
+/// Parses the contents of an SRT file into its cues
+pub fn parse_srt(content: &str) -> Result<Vec<Cue>, SrtError> {
+    let mut cues = Vec::new();
+    let mut lines = content
+        .trim_start_matches('\u{feff}')
+        .lines()
+        .enumerate()
+        .map(|(n, l)| (n + 1, l.trim_end()))
+        .peekable();
+
+    loop {
+        // Skip the blank lines separating cues
+        while lines.next_if(|(_, l)| l.is_empty()).is_some() {}
+
+        let Some((line, index)) = lines.next() else {
+            break;
+        };
+        let index = index.trim().parse().map_err(|_| SrtError::InvalidIndex {
+            line,
+            found: index.to_string(),
+        })?;
+
+        let (timing_line, timing) = lines.next().ok_or(SrtError::MissingTiming { line })?;
+        if timing.is_empty() {
+            return Err(SrtError::MissingTiming { line });
+        }
+        let (start, end) = parse_timing(timing).ok_or_else(|| SrtError::InvalidTiming {
+            line: timing_line,
+            found: timing.to_string(),
+        })?;
+
+        let mut text = Vec::new();
+        while let Some((_, l)) = lines.next_if(|(_, l)| !l.is_empty()) {
+            text.push(l.to_string());
+        }
+
+        cues.push(Cue {
+            index,
+            start,
+            end,
+            text,
+            line,
+        });
+    }
+
+    Ok(cues)
+}
+
+/// Parses a `start --> end` line, ignoring any trailing positioning data
+fn parse_timing(timing: &str) -> Option<(Timestamp, Timestamp)> {
+    let (start, rest) = timing.split_once("-->")?;
+    let end = rest.split_whitespace().next()?;
+
+    Some((Timestamp::parse(start.trim())?, Timestamp::parse(end)?))
+}
+
+/// Offsets every cue by `offset_ms`, clamping timestamps that would become negative at zero
+pub fn shift_srt(cues: &mut [Cue], offset_ms: i64) {
+    for cue in cues {
+        cue.start = cue.start.shifted(offset_ms);
+        cue.end = cue.end.shifted(offset_ms);
+    }
+}
+
+/// Adds the cues of `more` after `cues`, offsetting them by the end of the last cue of `cues` and
+/// numbering them on from its last index
+pub fn append_srt(cues: &mut Vec<Cue>, mut more: Vec<Cue>) {
+    let offset = cues.iter().map(|cue| cue.end.0).max().unwrap_or(0);
+    let first_index = cues.last().map_or(0, |cue| cue.index);
+    shift_srt(&mut more, offset as i64);
+    for (i, cue) in more.iter_mut().enumerate() {
+        cue.index = first_index + i as u32 + 1;
+    }
+    cues.append(&mut more);
+}
+
+/// Serializes cues back into SRT format
+pub fn write_srt(cues: &[Cue]) -> String {
+    let mut out = String::new();
+    for cue in cues {
+        out.push_str(&format!("{}\n{} --> {}\n", cue.index, cue.start, cue.end));
+        for line in &cue.text {
+            out.push_str(line);
+            out.push('\n');
+        }
+        out.push('\n');
+    }
+    out
+}
+
+/// A problem found while validating an SRT file
+#[derive(Debug)]
+pub struct SrtIssue {
+    /// Line the problem was found on, or 0 for problems with the file as a whole
+    pub line: usize,
+    pub message: String,
+}
+
+impl Display for SrtIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.line == 0 {
+            f.write_str(&self.message)
+        } else {
+            write!(f, "line {}: {}", self.line, self.message)
+        }
+    }
+}
+
+impl From<SrtError> for SrtIssue {
+    fn from(value: SrtError) -> Self {
+        Self {
+            line: value.line(),
+            message: value.message(),
+        }
+    }
+}
+
+/// Reads and parses an SRT file in any detected encoding
+fn load(path: &Path) -> Result<Vec<Cue>, SrtIssue> {
+    let file_issue = |message: String| SrtIssue { line: 0, message };
+    let bytes = read(path).map_err(|e| file_issue(format!("unreadable: {e}")))?;
+    let content =
+        encoding::to_utf8(&bytes).ok_or_else(|| file_issue("unknown text encoding".to_string()))?;
+    Ok(parse_srt(content.text())?)
+}
+
+/// Finds the latest time any cue of an SRT file is shown until
+pub fn end_time(path: &Path) -> Result<Timestamp, SrtIssue> {
+    load(path)?
+        .iter()
+        .map(|cue| cue.end)
+        .max()
+        .ok_or_else(|| SrtIssue {
+            line: 0,
+            message: "no cues".to_string(),
+        })
+}
+
+/// Counts the cues of an SRT file
+pub fn cue_count(path: &Path) -> Result<usize, SrtIssue> {
+    Ok(load(path)?.len())
+}
+
+/// Checks that an SRT file parses, its indices count up from 1, every cue ends after it starts
+/// and no earlier than the previous cue starts, and no cue is empty
+pub fn validate(path: &Path) -> Result<(), Vec<SrtIssue>> {
+    let cues = load(path).map_err(|issue| vec![issue])?;
+
+    let mut issues = Vec::new();
+    if cues.is_empty() {
+        issues.push(SrtIssue {
+            line: 0,
+            message: "no cues".to_string(),
+        });
+    }
+    let mut previous: Option<&Cue> = None;
+    for (i, cue) in cues.iter().enumerate() {
+        let mut issue = |message: String| {
+            issues.push(SrtIssue {
+                line: cue.line,
+                message,
+            })
+        };
+        if cue.index as usize != i + 1 {
+            issue(format!("expected cue index {}, found {}", i + 1, cue.index));
+        }
+        if cue.end <= cue.start {
+            issue(format!(
+                "cue ends at {} before it starts at {}",
+                cue.end, cue.start
+            ));
+        }
+        if let Some(previous) = previous.filter(|p| cue.start < p.start) {
+            issue(format!(
+                "cue starts at {}, before the previous cue at {}",
+                cue.start, previous.start
+            ));
+        }
+        if cue.text.iter().all(|l| l.trim().is_empty()) {
+            issue("cue has no text".to_string());
+        }
+        previous = Some(cue);
+    }
+
+    if issues.is_empty() {
+        Ok(())
+    } else {
+        Err(issues)
+    }
+}
@@ -0,0 +1,108 @@
+use std::path::{Component, Path, PathBuf};
+
+/// Placeholders a template may contain, each written in braces such as `{stem}`
+const PLACEHOLDERS: &[&str] = &["stem", "lang", "ext", "tag"];
+
+/// Format string for subtitle targets relative to their media's directory, such as
+/// `Subs/{stem}.{lang}.{ext}`
+#[derive(Debug, Clone)]
+pub struct Template(String);
+
+/// Values substituted for the placeholders of a template
+#[derive(Debug)]
+pub struct Fields<'a> {
+    /// Media file name without its extension
+    pub stem: &'a str,
+    /// Language code of the subtitle
+    pub lang: Option<&'a str>,
+    /// Subtitle extension without the leading dot
+    pub ext: &'a str,
+    /// Track flags of the subtitle joined by dots, such as `forced` or `sdh`
+    pub tag: Option<&'a str>,
+}
+
+impl Template {
+    /// Checks that every placeholder is known and that the template stays inside the media's
+    /// directory
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut rest = s;
+        while let Some(start) = rest.find('{') {
+            let Some(end) = rest[start..].find('}') else {
+                return Err(format!("unclosed `{{` in `{s}`"));
+            };
+            let name = &rest[start + 1..start + end];
+            if !PLACEHOLDERS.contains(&name) {
+                return Err(format!(
+                    "unknown placeholder `{{{name}}}`, expected one of {}",
+                    PLACEHOLDERS
+                        .iter()
+                        .map(|p| format!("{{{p}}}"))
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                ));
+            }
+            rest = &rest[start + end + 1..];
+        }
+
+        let template = Self(s.to_string());
+        let sample = template.expand(&Fields {
+            stem: "stem",
+            lang: Some("en"),
+            ext: "srt",
+            tag: Some("forced"),
+        });
+        if !is_contained(Path::new(&sample)) {
+            return Err(format!(
+                "`{s}` must be a relative path that stays inside the media's directory"
+            ));
+        }
+        Ok(template)
+    }
+
+    /// Substitutes `fields` into the template. An empty placeholder also drops the dot before
+    /// it, so `{stem}.{lang}.{ext}` becomes `Show.srt` without a language
+    fn expand(&self, fields: &Fields) -> String {
+        let mut expanded = String::new();
+        let mut rest = self.0.as_str();
+        while let Some(start) = rest.find('{') {
+            expanded.push_str(&rest[..start]);
+            let end = start
+                + rest[start..]
+                    .find('}')
+                    .expect("placeholders closed on parse");
+            let value = match &rest[start + 1..end] {
+                "stem" => Some(fields.stem),
+                "lang" => fields.lang,
+                "ext" => Some(fields.ext),
+                "tag" => fields.tag,
+                _ => unreachable!("placeholders checked on parse"),
+            };
+            match value.filter(|v| !v.is_empty()) {
+                Some(value) => expanded.push_str(value),
+                None => {
+                    if expanded.ends_with('.') {
+                        expanded.pop();
+                    }
+                }
+            }
+            rest = &rest[end + 1..];
+        }
+        expanded.push_str(rest);
+        expanded
+    }
+
+    /// Builds the target in `dir` for `fields`, or `None` when the values would take it outside
+    /// of `dir`, such as a language code containing `..`
+    pub fn render(&self, dir: &Path, fields: &Fields) -> Option<PathBuf> {
+        let relative = PathBuf::from(self.expand(fields));
+        is_contained(&relative).then(|| dir.join(relative))
+    }
+}
+
+/// Whether a relative path only descends into folders, without `..` or a root
+fn is_contained(path: &Path) -> bool {
+    path.file_name().is_some()
+        && path
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
@@ -0,0 +1,184 @@
+use std::{error::Error, fmt::Display};
+
+use crate::srt::{Cue, Timestamp};
+
+#[derive(Debug)]
+pub enum VttError {
+    MissingHeader,
+    InvalidTiming { line: usize, found: String },
+    MissingTiming { line: usize },
+}
+
+impl Display for VttError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::MissingHeader => f.write_str("line 1: expected `WEBVTT`"),
+            Self::InvalidTiming { line, found } => write!(
+                f,
+                "line {line}: expected `HH:MM:SS.mmm --> HH:MM:SS.mmm`, found `{found}`"
+            ),
+            Self::MissingTiming { line } => write!(f, "line {line}: cue has no timing line"),
+        }
+    }
+}
+
+impl Error for VttError {}
+
+/// Parses an `HH:MM:SS.mmm` or `MM:SS.mmm` timestamp
+fn parse_timestamp(s: &str) -> Option<Timestamp> {
+    let (hms, millis) = s.split_once('.')?;
+    let parts: Vec<_> = hms.split(':').collect();
+    let (hours, minutes, seconds) = match parts[..] {
+        [minutes, seconds] => ("0", minutes, seconds),
+        [hours, minutes, seconds] => (hours, minutes, seconds),
+        _ => return None,
+    };
+    let hours: u64 = hours.parse().ok()?;
+    let minutes: u64 = minutes.parse().ok()?;
+    let seconds: u64 = seconds.parse().ok()?;
+    if minutes >= 60 || seconds >= 60 || millis.len() != 3 {
+        return None;
+    }
+    let millis: u64 = millis.parse().ok()?;
+
+    Some(Timestamp(
+        ((hours * 60 + minutes) * 60 + seconds) * 1000 + millis,
+    ))
+}
+
+/// Parses a `start --> end settings` line, also returning whether it had cue settings
+fn parse_timing(timing: &str) -> Option<(Timestamp, Timestamp, bool)> {
+    let (start, rest) = timing.split_once("-->")?;
+    let mut rest = rest.split_whitespace();
+    let end = rest.next()?;
+
+    Some((
+        parse_timestamp(start.trim())?,
+        parse_timestamp(end)?,
+        rest.next().is_some(),
+    ))
+}
+
+/// Removes the tags SRT has no equivalent for, such as voices `<v Name>` and classes `<c.red>`,
+/// keeping bold, italic, and underline, and unescapes entities. Also returns whether any tag was
+/// removed
+fn strip_tags(line: &str) -> (String, bool) {
+    const KEPT: &[&str] = &["b", "/b", "i", "/i", "u", "/u"];
+
+    let mut text = String::new();
+    let mut stripped = false;
+    let mut rest = line;
+    while let Some(open) = rest.find('<') {
+        text.push_str(&rest[..open]);
+        let Some(close) = rest[open..].find('>') else {
+            rest = &rest[open..];
+            break;
+        };
+        let tag = &rest[open..open + close + 1];
+        if KEPT.contains(&&tag[1..tag.len() - 1]) {
+            text.push_str(tag);
+        } else {
+            stripped = true;
+        }
+        rest = &rest[open + close + 1..];
+    }
+    text.push_str(rest);
+
+    let text = text
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&nbsp;", " ")
+        .replace("&amp;", "&");
+    (text, stripped)
+}
+
+/// Parses the contents of a WebVTT file into SRT cues numbered from 1. Cue settings, tags SRT
+/// can't represent, and style and region blocks are dropped, and how many were dropped is
+/// returned alongside
+pub fn parse_vtt(content: &str) -> Result<(Vec<Cue>, usize), VttError> {
+    let mut lines = content
+        .trim_start_matches('\u{feff}')
+        .lines()
+        .enumerate()
+        .map(|(n, l)| (n + 1, l.trim_end()))
+        .peekable();
+
+    match lines.next() {
+        Some((_, header))
+            if header == "WEBVTT"
+                || header.starts_with("WEBVTT ")
+                || header.starts_with("WEBVTT\t") => {}
+        _ => return Err(VttError::MissingHeader),
+    }
+
+    let mut cues = Vec::new();
+    let mut dropped = 0;
+    // The header may continue on the following lines
+    while lines.next_if(|(_, l)| !l.is_empty()).is_some() {}
+    loop {
+        // Skip the blank lines separating blocks
+        while lines.next_if(|(_, l)| l.is_empty()).is_some() {}
+
+        let Some((line, first)) = lines.next() else {
+            break;
+        };
+        let comment = first == "NOTE" || first.starts_with("NOTE ") || first.starts_with("NOTE\t");
+        if comment || first == "STYLE" || first == "REGION" {
+            dropped += usize::from(!comment);
+            while lines.next_if(|(_, l)| !l.is_empty()).is_some() {}
+            continue;
+        }
+
+        // Cues may start with an identifier before their timing
+        let (timing_line, timing) = if first.contains("-->") {
+            (line, first)
+        } else {
+            lines
+                .next()
+                .filter(|(_, l)| !l.is_empty())
+                .ok_or(VttError::MissingTiming { line })?
+        };
+        let (start, end, settings) =
+            parse_timing(timing).ok_or_else(|| VttError::InvalidTiming {
+                line: timing_line,
+                found: timing.to_string(),
+            })?;
+        dropped += usize::from(settings);
+
+        let mut text = Vec::new();
+        while let Some((_, l)) = lines.next_if(|(_, l)| !l.is_empty()) {
+            let (l, stripped) = strip_tags(l);
+            dropped += usize::from(stripped);
+            text.push(l);
+        }
+
+        cues.push(Cue {
+            index: cues.len() as u32 + 1,
+            start,
+            end,
+            text,
+            line,
+        });
+    }
+
+    Ok((cues, dropped))
+}
+
+/// Serializes cues into WebVTT format
+pub fn write_vtt(cues: &[Cue]) -> String {
+    let timestamp = |t: Timestamp| t.to_string().replacen(',', ".", 1);
+    let mut out = String::from("WEBVTT\n\n");
+    for cue in cues {
+        out.push_str(&format!(
+            "{} --> {}\n",
+            timestamp(cue.start),
+            timestamp(cue.end)
+        ));
+        for line in &cue.text {
+            out.push_str(line);
+            out.push('\n');
+        }
+        out.push('\n');
+    }
+    out
+}
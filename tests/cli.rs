@@ -0,0 +1,1609 @@
+use std::{
+    fs::{create_dir_all, read_link, read_to_string, write},
+    path::Path,
+};
+
+use assert_cmd::Command;
+use tempfile::TempDir;
+
+/// Writes a file, creating its parent folders
+fn touch(path: &Path, content: &str) {
+    create_dir_all(path.parent().unwrap()).unwrap();
+    write(path, content).unwrap();
+}
+
+fn stdout(assert: &assert_cmd::assert::Assert) -> String {
+    String::from_utf8_lossy(&assert.get_output().stdout).to_string()
+}
+
+/// A season of subtitle folders and media files in a temporary directory
+struct Library {
+    root: TempDir,
+}
+
+impl Library {
+    fn new() -> Self {
+        let library = Self {
+            root: TempDir::new().unwrap(),
+        };
+        create_dir_all(library.subs()).unwrap();
+        create_dir_all(library.media()).unwrap();
+        library
+    }
+
+    fn subs(&self) -> std::path::PathBuf {
+        self.root.path().join("subs")
+    }
+
+    fn media(&self) -> std::path::PathBuf {
+        self.root.path().join("media")
+    }
+
+    fn subtitle(&self, folder: &str, name: &str, content: &str) {
+        touch(&self.subs().join(folder).join(name), content);
+    }
+
+    fn episode(&self, stem: &str) {
+        touch(&self.media().join(format!("{stem}.mkv")), "");
+    }
+
+    /// Runs subsync without prompts, isolated from any config file, requiring no keyword
+    /// unless one is given
+    fn run(&self, args: &[&str]) -> assert_cmd::assert::Assert {
+        let mut command = Command::cargo_bin("subsync").unwrap();
+        command
+            .current_dir(self.root.path())
+            .env("XDG_CONFIG_HOME", self.root.path())
+            .arg(self.subs())
+            .arg(self.media())
+            .arg("--non-interactive")
+            .args(args);
+        if !args.contains(&"--keyword") {
+            command.args(["--keyword", ""]);
+        }
+        command.assert()
+    }
+}
+
+#[test]
+fn alphabetical_links_first_subtitle() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.subtitle("Show.S01E01", "b.srt", "longer subtitle");
+    library.subtitle("Show.S01E01", "a.srt", "short");
+
+    library.run(&["--strategy", "alphabetical"]).success();
+
+    let target = library.media().join("Show.S01E01.srt");
+    assert_eq!(
+        read_link(target).unwrap(),
+        library.subs().join("Show.S01E01").join("a.srt")
+    );
+}
+
+#[test]
+fn size_links_largest_subtitle() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.subtitle("Show.S01E01", "b.srt", "longer subtitle");
+    library.subtitle("Show.S01E01", "a.srt", "short");
+
+    library.run(&["--strategy", "size"]).success();
+
+    let target = library.media().join("Show.S01E01.srt");
+    assert_eq!(
+        read_link(target).unwrap(),
+        library.subs().join("Show.S01E01").join("b.srt")
+    );
+}
+
+#[test]
+fn keyword_filters_subtitles() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.subtitle("Show.S01E01", "a.srt", "first");
+    library.subtitle("Show.S01E01", "b.English.srt", "second");
+
+    library
+        .run(&["--strategy", "alphabetical", "--keyword", "english"])
+        .success();
+
+    let target = library.media().join("Show.S01E01.srt");
+    assert_eq!(
+        read_link(target).unwrap(),
+        library.subs().join("Show.S01E01").join("b.English.srt")
+    );
+}
+
+#[test]
+fn copy_writes_subtitle_contents() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.subtitle("Show.S01E01", "a.srt", "subtitle");
+
+    library
+        .run(&["--strategy", "alphabetical", "--copy"])
+        .success();
+
+    let target = library.media().join("Show.S01E01.srt");
+    assert!(!target.is_symlink());
+    assert_eq!(read_to_string(target).unwrap(), "subtitle");
+}
+
+#[test]
+fn existing_target_fails_without_overwrite() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.subtitle("Show.S01E01", "a.srt", "new");
+    let target = library.media().join("Show.S01E01.srt");
+    touch(&target, "old");
+
+    let assert = library
+        .run(&["--strategy", "alphabetical", "--copy"])
+        .code(3);
+
+    assert!(stdout(&assert).contains("Failed:"));
+
+    assert_eq!(read_to_string(&target).unwrap(), "old");
+}
+
+#[test]
+fn overwrite_needs_yes_without_prompts() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.subtitle("Show.S01E01", "a.srt", "new");
+    let target = library.media().join("Show.S01E01.srt");
+    touch(&target, "old");
+
+    library
+        .run(&["--strategy", "alphabetical", "--copy", "--overwrite"])
+        .success();
+    assert_eq!(read_to_string(&target).unwrap(), "old");
+
+    library
+        .run(&[
+            "--strategy",
+            "alphabetical",
+            "--copy",
+            "--overwrite",
+            "--yes",
+        ])
+        .success();
+    assert_eq!(read_to_string(&target).unwrap(), "new");
+    // The copy was renamed over the old target, leaving no temporary file behind
+    assert!(!library
+        .media()
+        .join(".Show.S01E01.srt.subsync-tmp")
+        .exists());
+}
+
+#[test]
+fn copy_overwrite_replaces_target_through_a_temporary_file() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.subtitle("Show.S01E01", "a.srt", "new");
+    let source = library.subs().join("Show.S01E01/a.srt");
+    let target = library.media().join("Show.S01E01.srt");
+    touch(&target, "old");
+
+    library
+        .run(&[
+            "--strategy",
+            "alphabetical",
+            "--copy",
+            "--overwrite",
+            "--yes",
+        ])
+        .success();
+    assert_eq!(read_to_string(&target).unwrap(), "new");
+    assert!(!target.is_symlink());
+    assert_eq!(read_to_string(&source).unwrap(), "new");
+    // The copy was renamed over the old target, leaving no temporary file behind
+    assert!(!library
+        .media()
+        .join(".Show.S01E01.srt.subsync-tmp")
+        .exists());
+    assert!(!std::fs::read_dir(library.media())
+        .unwrap()
+        .any(|entry| entry
+            .unwrap()
+            .file_name()
+            .to_string_lossy()
+            .ends_with(".subsync-tmp")));
+}
+
+#[cfg(unix)]
+#[test]
+fn overwrite_replaces_dangling_symlink() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.subtitle("Show.S01E01", "a.srt", "new");
+    let target = library.media().join("Show.S01E01.srt");
+    std::os::unix::fs::symlink(library.root.path().join("moved/a.srt"), &target).unwrap();
+
+    library
+        .run(&["--strategy", "alphabetical", "--overwrite", "--yes"])
+        .success();
+
+    assert_eq!(
+        read_link(&target).unwrap(),
+        library.subs().join("Show.S01E01").join("a.srt")
+    );
+}
+
+#[test]
+fn unmatched_stems_are_listed() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.episode("Show.S01E02");
+    library.subtitle("Show.S01E01", "a.srt", "subtitle");
+
+    let assert = library.run(&["--strategy", "alphabetical"]).code(3);
+
+    let stdout = stdout(&assert);
+    assert!(stdout.contains("Didn't match:"));
+    assert!(stdout.contains("- Show.S01E02"));
+
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("Found 1 subtitle folders for 2 media files"));
+    assert!(stderr.contains("No subtitles for Show.S01E02"));
+
+    assert!(library.media().join("Show.S01E01.srt").is_symlink());
+    assert!(!library.media().join("Show.S01E02.srt").exists());
+}
+
+#[test]
+fn single_mode_links_next_to_file() {
+    let library = Library::new();
+    library.episode("Movie");
+    library.subtitle("", "movie.srt", "subtitle");
+
+    Command::cargo_bin("subsync")
+        .unwrap()
+        .current_dir(library.root.path())
+        .env("XDG_CONFIG_HOME", library.root.path())
+        .arg(library.subs())
+        .arg(library.media().join("Movie.mkv"))
+        .args(["--non-interactive", "--keyword", "", "--strategy", "size"])
+        .assert()
+        .success();
+
+    assert!(library.media().join("Movie.srt").is_symlink());
+}
+
+#[test]
+fn missing_media_is_fatal() {
+    let library = Library::new();
+    library.subtitle("Show.S01E01", "a.srt", "subtitle");
+
+    library.run(&["--strategy", "alphabetical"]).code(1);
+}
+
+#[test]
+fn manifest_pairs_listed_entries() {
+    let library = Library::new();
+    library.episode("Show.S00E01");
+    library.episode("Show.S01E01");
+    library.subtitle("Special", "b.srt", "longer subtitle");
+    library.subtitle("Special", "a.srt", "short");
+    touch(
+        &library.root.path().join("manifest.toml"),
+        "media-dir = \"media\"\n\n[[entry]]\nsubtitles = \"subs/Special\"\nmedia = \"media/Show.S00E01.mkv\"\n",
+    );
+
+    let assert = Command::cargo_bin("subsync")
+        .unwrap()
+        .current_dir(library.root.path())
+        .env("XDG_CONFIG_HOME", library.root.path())
+        .args(["--manifest", "manifest.toml", "--non-interactive"])
+        .args(["--keyword", "", "--strategy", "size"])
+        .assert()
+        .code(3);
+
+    assert!(stdout(&assert).contains("- Show.S01E01"));
+    assert_eq!(
+        read_link(library.media().join("Show.S00E01.srt")).unwrap(),
+        Path::new("subs/Special/b.srt")
+    );
+}
+
+#[test]
+fn double_episode_merges_both_subtitles() {
+    let library = Library::new();
+    library.episode("Show.S01E01E02");
+    library.subtitle(
+        "Show.S01E01",
+        "a.srt",
+        "1\n00:00:01,000 --> 00:00:02,000\nFirst\n",
+    );
+    library.subtitle(
+        "Show.S01E02",
+        "a.srt",
+        "1\n00:00:03,000 --> 00:00:04,000\nSecond\n",
+    );
+
+    library
+        .run(&[
+            "--strategy",
+            "alphabetical",
+            "--match-by",
+            "episode",
+            "--merge",
+        ])
+        .success();
+
+    assert_eq!(
+        read_to_string(library.media().join("Show.S01E01E02.srt")).unwrap(),
+        "1\n00:00:01,000 --> 00:00:02,000\nFirst\n\n2\n00:00:05,000 --> 00:00:06,000\nSecond\n\n"
+    );
+}
+
+#[test]
+fn double_episode_uses_first_found_without_merge() {
+    let library = Library::new();
+    library.episode("Show.S01E03-E04");
+    library.subtitle("Show.S01E04", "a.srt", "subtitle");
+
+    let assert = library
+        .run(&["--strategy", "alphabetical", "--match-by", "episode"])
+        .success();
+
+    assert!(stdout(&assert).contains("Only found subtitles for one episode"));
+    assert_eq!(
+        read_link(library.media().join("Show.S01E03-E04.srt")).unwrap(),
+        library.subs().join("Show.S01E04").join("a.srt")
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn no_fallback_reports_the_link_error() {
+    let library = Library::new();
+    // The target, with its language tag, is too long a name to link
+    let stem = format!("Show.S01E01.{}", "x".repeat(238));
+    library.episode(&stem);
+    library.subtitle(&stem, "a.srt", "a");
+
+    let assert = library
+        .run(&[
+            "--strategy",
+            "alphabetical",
+            "--lang",
+            "en",
+            "--no-fallback",
+        ])
+        .code(3);
+
+    assert!(String::from_utf8_lossy(&assert.get_output().stderr).contains("File name too long"));
+    assert!(!stdout(&assert).contains("copying instead"));
+    assert_eq!(std::fs::read_dir(library.media()).unwrap().count(), 1);
+}
+
+#[test]
+fn match_by_episode_still_pairs_folders_by_name() {
+    let library = Library::new();
+    library.episode("Pilot");
+    library.episode("Show.S01E01");
+    library.subtitle("Pilot", "a.srt", "pilot");
+    library.subtitle("1x01", "a.srt", "episode");
+
+    library
+        .run(&["--strategy", "alphabetical", "--match-by", "episode"])
+        .success();
+
+    assert_eq!(
+        read_link(library.media().join("Pilot.srt")).unwrap(),
+        library.subs().join("Pilot").join("a.srt")
+    );
+    assert_eq!(
+        read_link(library.media().join("Show.S01E01.srt")).unwrap(),
+        library.subs().join("1x01").join("a.srt")
+    );
+}
+
+#[test]
+fn follow_symlinks_scans_linked_directories() {
+    let library = Library::new();
+    library.subtitle("Show.S01E01", "a.srt", "subtitle");
+    let nas = library.root.path().join("nas");
+    touch(&nas.join("Show.S01E01.mkv"), "");
+    std::os::unix::fs::symlink(&nas, library.media().join("Season 1")).unwrap();
+
+    library
+        .run(&["--strategy", "alphabetical", "--recursive"])
+        .code(1);
+
+    library
+        .run(&[
+            "--strategy",
+            "alphabetical",
+            "--recursive",
+            "--follow-symlinks",
+        ])
+        .success();
+    assert!(nas.join("Show.S01E01.srt").is_symlink());
+}
+
+#[test]
+fn sidecar_records_source_and_is_pruned() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.subtitle("Show.S01E01", "a.srt", "subtitle");
+
+    library
+        .run(&["--strategy", "alphabetical", "--copy", "--sidecar"])
+        .success();
+
+    let sidecar = library.media().join("Show.S01E01.srt.subsync.json");
+    let recorded: serde_json::Value =
+        serde_json::from_str(&read_to_string(&sidecar).unwrap()).unwrap();
+    assert_eq!(
+        recorded["source"],
+        library
+            .subs()
+            .join("Show.S01E01")
+            .join("a.srt")
+            .to_string_lossy()
+            .as_ref()
+    );
+    assert_eq!(recorded["size"], 8);
+    assert_eq!(recorded["strategy"], "alphabetical");
+    assert_eq!(recorded["operation"], "copy");
+    assert!(recorded["created_at"].as_u64().unwrap() > 0);
+
+    std::fs::remove_file(library.media().join("Show.S01E01.mkv")).unwrap();
+    library.episode("Other.S01E01");
+    library.run(&["--strategy", "alphabetical", "--prune", "--yes"]);
+
+    assert!(!library.media().join("Show.S01E01.srt").exists());
+    assert!(!sidecar.exists());
+}
+
+#[test]
+fn sub_depth_finds_nested_subtitles() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.subtitle("Show.S01E01/Forced", "a.srt", "nested");
+
+    library.run(&["--strategy", "alphabetical"]).code(3);
+
+    library
+        .run(&["--strategy", "alphabetical", "--sub-depth", "1"])
+        .success();
+    assert_eq!(
+        read_link(library.media().join("Show.S01E01.srt")).unwrap(),
+        library
+            .subs()
+            .join("Show.S01E01")
+            .join("Forced")
+            .join("a.srt")
+    );
+}
+
+#[test]
+fn hash_prints_opensubtitles_hash() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    touch(
+        &library.media().join("Show.S01E02.mkv"),
+        &"\0".repeat(128 * 1024),
+    );
+
+    let assert = Command::cargo_bin("subsync")
+        .unwrap()
+        .current_dir(library.root.path())
+        .env("XDG_CONFIG_HOME", library.root.path())
+        .args(["--hash", "media"])
+        .assert()
+        .success();
+
+    let stdout = stdout(&assert);
+    let lines: Vec<_> = stdout.lines().filter(|l| l.contains('\t')).collect();
+    assert_eq!(
+        lines,
+        [
+            "Show.S01E01\t0000000000000000\t0\tmedia/Show.S01E01.mkv",
+            "Show.S01E02\t0000000000020000\t131072\tmedia/Show.S01E02.mkv",
+        ]
+    );
+}
+
+#[test]
+fn folder_matches_part_of_dotted_stem() {
+    let library = Library::new();
+    library.episode("Show.S01E01.1080p");
+    library.episode("Show.S01E02.1080p");
+    library.subtitle("S01E01", "a.srt", "subtitle");
+
+    library.run(&["--strategy", "alphabetical"]).code(3);
+
+    assert_eq!(
+        read_link(library.media().join("Show.S01E01.1080p.srt")).unwrap(),
+        library.subs().join("S01E01").join("a.srt")
+    );
+}
+
+#[test]
+fn media_without_extension_keeps_whole_name() {
+    let library = Library::new();
+    touch(&library.media().join("Movie"), "");
+    library.subtitle("", "movie.srt", "subtitle");
+
+    Command::cargo_bin("subsync")
+        .unwrap()
+        .current_dir(library.root.path())
+        .env("XDG_CONFIG_HOME", library.root.path())
+        .arg(library.subs())
+        .arg(library.media().join("Movie"))
+        .args(["--non-interactive", "--keyword", "", "--strategy", "size"])
+        .assert()
+        .success();
+
+    assert!(library.media().join("Movie.srt").is_symlink());
+}
+
+#[test]
+fn hidden_files_are_ignored() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.episode("._Show.S01E01");
+    library.subtitle("Show.S01E01", "._a.srt", "metadata");
+    library.subtitle("Show.S01E01", "b.srt", "subtitle");
+
+    library
+        .run(&["--strategy", "alphabetical", "--match-by", "episode"])
+        .success();
+
+    assert_eq!(
+        read_link(library.media().join("Show.S01E01.srt")).unwrap(),
+        library.subs().join("Show.S01E01").join("b.srt")
+    );
+    assert!(!library.media().join("._Show.S01E01.srt").exists());
+}
+
+#[test]
+fn include_hidden_considers_dotfiles() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    touch(&library.subs().join(".DS_Store"), "");
+    library.subtitle("Show.S01E01", ".a.srt", "hidden");
+    library.subtitle("Show.S01E01", "b.srt", "subtitle");
+
+    // The stray file doesn't count as an unmatched subtitle folder
+    let assert = library.run(&["--strategy", "alphabetical"]).success();
+    assert!(!String::from_utf8_lossy(&assert.get_output().stderr).contains("DS_Store"));
+    assert_eq!(
+        read_link(library.media().join("Show.S01E01.srt")).unwrap(),
+        library.subs().join("Show.S01E01").join("b.srt")
+    );
+
+    library
+        .run(&[
+            "--strategy",
+            "alphabetical",
+            "--include-hidden",
+            "--overwrite",
+            "--yes",
+        ])
+        .success();
+    assert_eq!(
+        read_link(library.media().join("Show.S01E01.srt")).unwrap(),
+        library.subs().join("Show.S01E01").join(".a.srt")
+    );
+}
+
+#[test]
+fn reverse_link_links_media_into_subtitle_folder() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.subtitle("Show.S01E01", "a.srt", "subtitle");
+
+    library
+        .run(&["--strategy", "alphabetical", "--reverse-link"])
+        .success();
+
+    assert_eq!(
+        read_link(library.subs().join("Show.S01E01").join("Show.S01E01.mkv")).unwrap(),
+        library.media().join("Show.S01E01.mkv")
+    );
+    assert!(!library.media().join("Show.S01E01.srt").exists());
+}
+
+#[test]
+fn source_is_placed_for_every_media_file() {
+    let library = Library::new();
+    library.episode("Movie.Part1");
+    library.episode("Movie.Part2");
+    let source = library.root.path().join("movie.srt");
+    touch(&source, "subtitle");
+
+    let assert = Command::cargo_bin("subsync")
+        .unwrap()
+        .current_dir(library.root.path())
+        .env("XDG_CONFIG_HOME", library.root.path())
+        .arg("--source")
+        .arg(&source)
+        .arg(library.media())
+        .assert()
+        .success();
+
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("same subtitle will be placed for all 2 media files"));
+    for stem in ["Movie.Part1", "Movie.Part2"] {
+        assert_eq!(
+            read_link(library.media().join(format!("{stem}.srt"))).unwrap(),
+            source
+        );
+    }
+}
+
+#[test]
+fn lang_dirs_place_one_subtitle_per_language() {
+    let library = Library::new();
+    library.episode("Movie");
+    library.subtitle("Movie/English", "2_English.srt", "english");
+    library.subtitle("Movie/spa", "3_Spanish.srt", "spanish");
+    library.subtitle("Movie/Extras", "commentary.srt", "commentary");
+
+    library
+        .run(&["--strategy", "alphabetical", "--lang-dirs"])
+        .success();
+
+    assert!(library.media().join("Movie.en.srt").is_symlink());
+    assert!(library.media().join("Movie.es.srt").is_symlink());
+    assert!(!library.media().join("Movie.srt").exists());
+}
+
+#[test]
+fn stats_compares_strategies_without_placing() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.subtitle(
+        "Show.S01E01",
+        "a.srt",
+        "1\n00:00:01,000 --> 00:00:02,000\nshort\n\n2\n00:45:00,000 --> 00:45:01,000\nlong\n",
+    );
+    library.subtitle(
+        "Show.S01E01",
+        "b.srt",
+        "1\n00:00:01,000 --> 00:00:05,000\nthe largest file of the folder by some margin\n",
+    );
+
+    let assert = library.run(&["--stats"]).success();
+
+    let stdout = stdout(&assert);
+    let row = stdout
+        .lines()
+        .find(|l| l.starts_with("Show.S01E01"))
+        .unwrap();
+    assert_eq!(
+        row.split_whitespace().collect::<Vec<_>>(),
+        ["Show.S01E01", "a.srt", "b.srt", "a.srt"]
+    );
+    assert!(!library.media().join("Show.S01E01.srt").exists());
+}
+
+#[test]
+fn sub_out_places_targets_in_separate_directory() {
+    let library = Library::new();
+    library.episode("Season 1/Show.S01E01");
+    library.subtitle("Show.S01E01", "a.srt", "subtitle");
+    let sub_out = library.root.path().join("external");
+
+    library
+        .run(&[
+            "--strategy",
+            "alphabetical",
+            "--recursive",
+            "--sub-out",
+            sub_out.to_str().unwrap(),
+            "--mirror",
+        ])
+        .success();
+
+    assert!(sub_out
+        .join("Season 1")
+        .join("Show.S01E01.srt")
+        .is_symlink());
+    assert!(!library
+        .media()
+        .join("Season 1")
+        .join("Show.S01E01.srt")
+        .exists());
+}
+
+#[test]
+fn dry_run_report_lists_planned_operations() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.subtitle("Show.S01E01", "a.srt", "subtitle");
+    let target = library.media().join("Show.S01E01.srt");
+    touch(&target, "old");
+    let report = library.root.path().join("report.json");
+
+    library
+        .run(&[
+            "--strategy",
+            "alphabetical",
+            "--dry-run",
+            "--overwrite",
+            "--backup",
+            "--report",
+            report.to_str().unwrap(),
+        ])
+        .success();
+
+    let report: serde_json::Value =
+        serde_json::from_str(&read_to_string(&report).unwrap()).unwrap();
+    assert_eq!(report["executed"], false);
+    let entry = &report["entries"][0];
+    assert_eq!(entry["operation"], "symlink");
+    assert_eq!(entry["overwritten"], true);
+    assert_eq!(
+        entry["backup"],
+        library
+            .media()
+            .join("Show.S01E01.srt.bak")
+            .to_str()
+            .unwrap()
+    );
+    assert_eq!(read_to_string(&target).unwrap(), "old");
+}
+
+#[test]
+fn media_ext_none_pairs_extensionless_media() {
+    let library = Library::new();
+    touch(&library.media().join("Show S01E01"), "");
+    touch(&library.media().join("Show S01E02"), "");
+    library.subtitle("Show S01E01", "a.srt", "subtitle");
+
+    library
+        .run(&["--strategy", "alphabetical", "--media-ext", "mkv,none"])
+        .code(3);
+
+    assert!(library.media().join("Show S01E01.srt").is_symlink());
+    assert!(!library.media().join("Show S01E02.srt").exists());
+}
+
+#[test]
+fn ext_none_is_rejected_for_subtitles() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    touch(&library.subs().join("Show.S01E01").join("subtitle"), "a");
+
+    let assert = library
+        .run(&["--strategy", "alphabetical", "--ext", "none"])
+        .code(2);
+
+    assert!(String::from_utf8_lossy(&assert.get_output().stderr).contains("--media-ext"));
+}
+
+#[test]
+fn rules_override_keyword_and_strategy_per_folder() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.episode("Show.S01E02");
+    library.subtitle("Show.S01E01", "a.srt", "subtitle");
+    library.subtitle("Show.S01E01", "b.srt", "larger subtitle");
+    library.subtitle("Show.S01E02", "a.srt", "subtitle");
+    library.subtitle("Show.S01E02", "b.english.srt", "english");
+    library.subtitle("Show.S01E02", "c.english.srt", "larger english subtitle");
+    let rules = library.root.path().join("rules.toml");
+    touch(
+        &rules,
+        "[[rule]]\nfolder = \"*e02\"\nkeyword = \"English\"\nstrategy = \"size\"\n",
+    );
+
+    library
+        .run(&[
+            "--strategy",
+            "alphabetical",
+            "--rules",
+            rules.to_str().unwrap(),
+        ])
+        .success();
+
+    assert_eq!(
+        read_link(library.media().join("Show.S01E01.srt")).unwrap(),
+        library.subs().join("Show.S01E01").join("a.srt")
+    );
+    assert_eq!(
+        read_link(library.media().join("Show.S01E02.srt")).unwrap(),
+        library.subs().join("Show.S01E02").join("c.english.srt")
+    );
+}
+
+#[test]
+fn convert_rewrites_webvtt_as_srt() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.subtitle(
+        "Show.S01E01",
+        "a.vtt",
+        "WEBVTT\n\nSTYLE\n::cue { color: red }\n\nintro\n01:02.500 --> 00:01:04.000 line:0\n<v Host>Hello</v> &amp; <i>welcome</i>\n",
+    );
+
+    let assert = library
+        .run(&[
+            "--strategy",
+            "alphabetical",
+            "--ext",
+            "vtt",
+            "--out-ext",
+            "srt",
+            "--convert",
+        ])
+        .success();
+
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(stderr.contains("Dropped 3 cue settings, tags, or style blocks"));
+    assert!(!stderr.contains("without converting"));
+
+    let target = library.media().join("Show.S01E01.srt");
+    assert!(!target.is_symlink());
+    assert_eq!(
+        read_to_string(target).unwrap(),
+        "1\n00:01:02,500 --> 00:01:04,000\nHello & <i>welcome</i>\n\n"
+    );
+}
+
+#[test]
+fn flat_subtitles_match_media_by_episode() {
+    let library = Library::new();
+    library.episode("Show.S01E01.1080p");
+    library.episode("Show.S01E02.1080p");
+    touch(&library.subs().join("show - s01e01 - pilot.srt"), "first");
+    touch(&library.subs().join("show - s01e02 - second.srt"), "second");
+
+    library.run(&["--strategy", "alphabetical"]).success();
+
+    assert_eq!(
+        read_link(library.media().join("Show.S01E01.1080p.srt")).unwrap(),
+        library.subs().join("show - s01e01 - pilot.srt")
+    );
+    assert_eq!(
+        read_link(library.media().join("Show.S01E02.1080p.srt")).unwrap(),
+        library.subs().join("show - s01e02 - second.srt")
+    );
+}
+
+#[test]
+fn quiet_summary_only_prints_result_line() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.episode("Show.S01E02");
+    library.subtitle("Show.S01E01", "a.srt", "subtitle");
+
+    let assert = library
+        .run(&["--strategy", "alphabetical", "--quiet-summary-only"])
+        .code(3);
+
+    let stdout = stdout(&assert);
+    assert_eq!(stdout.lines().count(), 1);
+    assert!(stdout.contains("Completed with 1 matches"));
+    assert!(assert.get_output().stderr.is_empty());
+}
+
+#[test]
+fn specials_pair_with_season_zero() {
+    let library = Library::new();
+    touch(
+        &library.subs().join("Specials/Show.S00E01/a.srt"),
+        "special",
+    );
+    touch(
+        &library.subs().join("Season 01/Show.S01E01/a.srt"),
+        "regular",
+    );
+    touch(&library.media().join("Season 00/Show.S00E01.mkv"), "");
+    touch(&library.media().join("Season 01/Show.S01E01.mkv"), "");
+    let special = library.media().join("Season 00/Show.S00E01.srt");
+
+    library
+        .run(&[
+            "--strategy",
+            "alphabetical",
+            "--multi-season",
+            "--exclude-specials",
+        ])
+        .success();
+    assert!(!special.exists());
+    assert!(library
+        .media()
+        .join("Season 01/Show.S01E01.srt")
+        .is_symlink());
+
+    let assert = library
+        .run(&[
+            "--strategy",
+            "alphabetical",
+            "--multi-season",
+            "--skip-existing",
+        ])
+        .success();
+    assert!(special.is_symlink());
+    assert!(stdout(&assert).contains("Season 00: 1 matched, 0 failed, 0 unmatched"));
+}
+
+#[test]
+fn preserve_mtime_keeps_source_modification_time() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.subtitle("Show.S01E01", "a.srt", "subtitle");
+    let source = library.subs().join("Show.S01E01").join("a.srt");
+    let modified = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000);
+    std::fs::File::options()
+        .write(true)
+        .open(&source)
+        .unwrap()
+        .set_modified(modified)
+        .unwrap();
+
+    library
+        .run(&["--strategy", "alphabetical", "--copy", "--preserve-mtime"])
+        .success();
+
+    let target = library.media().join("Show.S01E01.srt");
+    assert_eq!(
+        std::fs::metadata(target).unwrap().modified().unwrap(),
+        modified
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn dedup_hardlinks_identical_copies() {
+    use std::os::unix::fs::MetadataExt;
+
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.episode("Show.S01E02");
+    library.subtitle("Show.S01E01", "forced.srt", "forced");
+    library.subtitle("Show.S01E02", "forced.srt", "forced");
+
+    let assert = library
+        .run(&["--strategy", "alphabetical", "--copy", "--dedup"])
+        .success();
+
+    let inode = |stem: &str| {
+        std::fs::metadata(library.media().join(format!("{stem}.srt")))
+            .unwrap()
+            .ino()
+    };
+    assert_eq!(inode("Show.S01E01"), inode("Show.S01E02"));
+    assert!(stdout(&assert).contains("6 B saved by hardlinking 1 duplicate copy"));
+}
+
+#[cfg(unix)]
+#[test]
+fn dedup_keeps_the_first_copy_modification_time() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.episode("Show.S01E02");
+    library.subtitle("Show.S01E01", "forced.srt", "forced");
+    library.subtitle("Show.S01E02", "forced.srt", "forced");
+    let set_modified = |folder: &str, secs: u64| {
+        let modified = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(secs);
+        std::fs::File::options()
+            .write(true)
+            .open(library.subs().join(folder).join("forced.srt"))
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        modified
+    };
+    let first = set_modified("Show.S01E01", 1_000_000);
+    set_modified("Show.S01E02", 2_000_000);
+
+    library
+        .run(&[
+            "--strategy",
+            "alphabetical",
+            "--copy",
+            "--dedup",
+            "--preserve-mtime",
+            "--jobs",
+            "1",
+        ])
+        .success();
+
+    let target = library.media().join("Show.S01E01.srt");
+    assert_eq!(
+        std::fs::metadata(target).unwrap().modified().unwrap(),
+        first
+    );
+}
+
+#[test]
+fn contains_matches_folders_by_episode_token() {
+    let library = Library::new();
+    library.episode("Show.2014.S01E02.1080p");
+    library.episode("Show.2014.S01E03.1080p");
+    library.episode("Show.2014.S02E03.1080p");
+    library.subtitle("01", "a.srt", "none");
+    library.subtitle("02", "a.srt", "second");
+    library.subtitle("03", "a.srt", "ambiguous");
+
+    let assert = library
+        .run(&["--strategy", "alphabetical", "--contains"])
+        .code(3);
+
+    assert_eq!(
+        read_link(library.media().join("Show.2014.S01E02.1080p.srt")).unwrap(),
+        library.subs().join("02").join("a.srt")
+    );
+    assert!(stdout(&assert)
+        .contains("Ambiguous match for 03: Show.2014.S01E03.1080p, Show.2014.S02E03.1080p"));
+    assert!(!library.media().join("Show.2014.S01E03.1080p.srt").exists());
+}
+
+#[test]
+fn resume_skips_media_finished_earlier() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.episode("Show.S01E02");
+    library.subtitle("Show.S01E01", "a.srt", "first");
+    library.subtitle("Show.S01E02", "a.srt", "second");
+    let progress = library.media().join(".subsync-resume");
+    touch(
+        &progress,
+        &format!(
+            "{}\n",
+            library.media().join("Show.S01E01.mkv").to_str().unwrap()
+        ),
+    );
+
+    library
+        .run(&["--strategy", "alphabetical", "--resume"])
+        .success();
+
+    assert!(!library.media().join("Show.S01E01.srt").exists());
+    assert!(library.media().join("Show.S01E02.srt").is_symlink());
+    assert!(!progress.exists());
+}
+
+#[test]
+fn summary_names_the_strategy_that_selected() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.subtitle("Show.S01E01", "a.srt", "a");
+    library.subtitle("Show.S01E01", "b.srt", "bb");
+    let report = library.root.path().join("report.json");
+
+    let assert = library
+        .run(&["--strategy", "forced", "--report", report.to_str().unwrap()])
+        .success();
+
+    assert!(stdout(&assert)
+        .contains("✔ Show.S01E01  symlinked  a.srt (1 B) by forced, fell back to alphabetical"));
+    let report: serde_json::Value = serde_json::from_str(&read_to_string(report).unwrap()).unwrap();
+    assert_eq!(
+        report["entries"][0]["strategy"],
+        "forced, fell back to alphabetical"
+    );
+}
+
+#[test]
+fn max_size_excludes_oversized_subtitles() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.episode("Show.S01E02");
+    library.subtitle("Show.S01E01", "english.srt", "subtitle");
+    library.subtitle("Show.S01E01", "all.srt", "every language merged");
+    library.subtitle("Show.S01E02", "all.srt", "every language merged");
+
+    let assert = library
+        .run(&["--strategy", "size", "--min-size", "2", "--max-size", "10"])
+        .code(3);
+
+    assert_eq!(
+        read_link(library.media().join("Show.S01E01.srt")).unwrap(),
+        library.subs().join("Show.S01E01").join("english.srt")
+    );
+    assert!(stdout(&assert).contains("between 2 B and 10 B"));
+}
+
+#[test]
+fn natural_sort_orders_numbers_by_value_and_ignores_accents() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.episode("Show.S01E02");
+    library.subtitle("Show.S01E01", "Part10.srt", "a");
+    library.subtitle("Show.S01E01", "part2.srt", "a");
+    library.subtitle("Show.S01E02", "Fox.srt", "a");
+    library.subtitle("Show.S01E02", "Écho.srt", "a");
+
+    library
+        .run(&["--strategy", "alphabetical", "--natural-sort"])
+        .success();
+
+    assert_eq!(
+        read_link(library.media().join("Show.S01E01.srt")).unwrap(),
+        library.subs().join("Show.S01E01").join("part2.srt")
+    );
+    assert_eq!(
+        read_link(library.media().join("Show.S01E02.srt")).unwrap(),
+        library.subs().join("Show.S01E02").join("Écho.srt")
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn match_command_chooses_subtitle_for_each_stem() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.episode("Show.S01E02");
+    library.subtitle("pool", "Show.S01E01.srt", "a");
+    library.subtitle("pool", "other.srt", "a");
+    let command = r#"sed -n 's/.*"stem":"\([^"]*\)".*/pool\/\1.srt/p'"#;
+
+    let assert = library.run(&["--match-command", command]).code(3);
+
+    assert_eq!(
+        read_link(library.media().join("Show.S01E01.srt")).unwrap(),
+        library.subs().join("pool").join("Show.S01E01.srt")
+    );
+    assert!(!library.media().join("Show.S01E02.srt").exists());
+    let stderr = String::from_utf8_lossy(&assert.get_output().stderr);
+    assert!(
+        stderr.contains("Match command failed for Show.S01E02: pool/Show.S01E02.srt isn't a file")
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn read_only_media_folder_fails_its_media() {
+    use std::{fs::set_permissions, os::unix::fs::PermissionsExt};
+
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.subtitle("Show.S01E01", "a.srt", "a");
+    set_permissions(library.media(), PermissionsExt::from_mode(0o555)).unwrap();
+
+    let assert = library.run(&["--strategy", "alphabetical"]).code(3);
+    set_permissions(library.media(), PermissionsExt::from_mode(0o755)).unwrap();
+
+    assert!(stdout(&assert).contains("Failed:"));
+    assert!(String::from_utf8_lossy(&assert.get_output().stderr).contains("it is read-only"));
+    assert!(!library.media().join("Show.S01E01.srt").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn media_folder_writable_only_by_others_fails_its_media() {
+    use std::{fs::set_permissions, os::unix::fs::PermissionsExt};
+
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.subtitle("Show.S01E01", "a.srt", "a");
+    // Group and others may write, but not the owner running the test
+    set_permissions(library.media(), PermissionsExt::from_mode(0o577)).unwrap();
+    let writable = std::fs::File::create(library.media().join("probe")).is_ok();
+
+    let assert = library.run(&["--strategy", "alphabetical"]);
+    set_permissions(library.media(), PermissionsExt::from_mode(0o755)).unwrap();
+    // Permissions don't apply to root
+    if writable {
+        return;
+    }
+
+    let assert = assert.code(3);
+    assert!(String::from_utf8_lossy(&assert.get_output().stderr).contains("it is read-only"));
+    assert!(!library.media().join("Show.S01E01.srt").exists());
+}
+
+#[test]
+fn mkdir_creates_missing_media_folder() {
+    let library = Library::new();
+    library.subtitle("Special", "a.srt", "a");
+    touch(
+        &library.root.path().join("manifest.toml"),
+        "[[entry]]\nsubtitles = \"subs/Special\"\nmedia = \"Specials/Show.S00E01.mkv\"\n",
+    );
+    let sync = |mkdir: bool| {
+        let mut command = Command::cargo_bin("subsync").unwrap();
+        command
+            .current_dir(library.root.path())
+            .env("XDG_CONFIG_HOME", library.root.path())
+            .args(["--manifest", "manifest.toml", "--non-interactive"])
+            .args(["--keyword", "", "--strategy", "alphabetical"]);
+        if mkdir {
+            command.arg("--mkdir");
+        }
+        command.assert()
+    };
+
+    let assert = sync(false).code(3);
+    assert!(String::from_utf8_lossy(&assert.get_output().stderr).contains("Use --mkdir"));
+
+    sync(true).success();
+    assert_eq!(
+        read_link(library.root.path().join("Specials/Show.S00E01.srt")).unwrap(),
+        Path::new("subs/Special/a.srt")
+    );
+}
+
+#[test]
+fn explain_prints_keys_and_pairings() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.episode("Show.S01E02");
+    library.subtitle("show s01e01", "a.srt", "a");
+    library.subtitle("Extras", "a.srt", "a");
+
+    let assert = library
+        .run(&[
+            "--strategy",
+            "alphabetical",
+            "--normalize",
+            "separators",
+            "--explain",
+        ])
+        .code(3);
+
+    let stdout = stdout(&assert);
+    assert!(stdout.contains(" - Show.S01E02: key `show s01e02`, episode S01E02"));
+    assert!(stdout.contains(
+        " - show s01e01: key `show s01e01`, episode S01E01: matched Show.S01E01 by name"
+    ));
+    assert!(stdout.contains(" - Extras: key `extras`, no episode: unmatched"));
+}
+
+#[cfg(unix)]
+#[test]
+fn locked_output_refuses_a_second_run() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.subtitle("Show.S01E01", "a.srt", "a");
+    let lock_path = library.media().join(".subsync.lock");
+    let lock = std::fs::File::create(&lock_path).unwrap();
+    lock.lock().unwrap();
+    write(&lock_path, std::process::id().to_string()).unwrap();
+
+    let assert = library.run(&["--strategy", "alphabetical"]).code(1);
+    assert!(String::from_utf8_lossy(&assert.get_output().stderr).contains("Use --wait"));
+
+    // This process is still running, so its lock isn't replaced
+    let assert = library
+        .run(&["--strategy", "alphabetical", "--force-unlock"])
+        .code(1);
+    assert!(String::from_utf8_lossy(&assert.get_output().stderr).contains("still changing"));
+    assert!(!library.media().join("Show.S01E01.srt").exists());
+
+    let mut exited = std::process::Command::new("true").spawn().unwrap();
+    exited.wait().unwrap();
+    write(&lock_path, exited.id().to_string()).unwrap();
+    let assert = library
+        .run(&["--strategy", "alphabetical", "--force-unlock"])
+        .success();
+    assert!(String::from_utf8_lossy(&assert.get_output().stderr)
+        .contains("isn't running on this machine"));
+    assert!(library.media().join("Show.S01E01.srt").exists());
+    // The lock file goes with the lock
+    assert!(!lock_path.exists());
+}
+
+#[test]
+fn media_list_replaces_scanning_the_output() {
+    let library = Library::new();
+    library.subtitle("Show.S01E01", "a.srt", "a");
+    library.subtitle("Show.S01E02", "a.srt", "a");
+    let list = library.root.path().join("media.json");
+    touch(
+        &list,
+        r#"["Show.S01E01.mkv", "season/Show.S01E02.mkv", "notes.txt"]"#,
+    );
+    let sub_out = library.root.path().join("out");
+
+    Command::cargo_bin("subsync")
+        .unwrap()
+        .current_dir(library.root.path())
+        .env("XDG_CONFIG_HOME", library.root.path())
+        .arg(library.subs())
+        .arg(library.root.path().join("remote"))
+        .args([
+            "--non-interactive",
+            "--keyword",
+            "",
+            "--strategy",
+            "alphabetical",
+        ])
+        .arg("--media-list")
+        .arg(&list)
+        .arg("--sub-out")
+        .arg(&sub_out)
+        .assert()
+        .success();
+
+    for stem in ["Show.S01E01", "Show.S01E02"] {
+        assert_eq!(
+            read_link(sub_out.join(format!("{stem}.srt"))).unwrap(),
+            library.subs().join(stem).join("a.srt")
+        );
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn skip_embedded_leaves_media_with_subtitle_streams() {
+    use std::{fs::set_permissions, os::unix::fs::PermissionsExt};
+
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.episode("Show.S01E02");
+    library.subtitle("Show.S01E01", "a.srt", "a");
+    library.subtitle("Show.S01E02", "a.srt", "a");
+    // Reports one subtitle stream in the first episode only
+    let ffprobe = library.root.path().join("bin").join("ffprobe");
+    touch(
+        &ffprobe,
+        "#!/bin/sh\nfor arg; do :; done\ncase \"$arg\" in *E01.mkv) echo 2;; esac\n",
+    );
+    set_permissions(&ffprobe, PermissionsExt::from_mode(0o755)).unwrap();
+    let path = format!(
+        "{}:{}",
+        ffprobe.parent().unwrap().to_string_lossy(),
+        std::env::var("PATH").unwrap()
+    );
+
+    let assert = Command::cargo_bin("subsync")
+        .unwrap()
+        .current_dir(library.root.path())
+        .env("XDG_CONFIG_HOME", library.root.path())
+        .env("PATH", path)
+        .arg(library.subs())
+        .arg(library.media())
+        .args([
+            "--non-interactive",
+            "--keyword",
+            "",
+            "--strategy",
+            "alphabetical",
+        ])
+        .arg("--skip-embedded")
+        .assert()
+        .success();
+
+    assert!(String::from_utf8_lossy(&assert.get_output().stderr)
+        .contains("Show.S01E01.mkv already has 1 embedded subtitle stream, skipping it"));
+    assert!(stdout(&assert).contains("✗ Show.S01E01  skipped"));
+    assert!(!library.media().join("Show.S01E01.srt").exists());
+    assert!(library.media().join("Show.S01E02.srt").exists());
+}
+
+#[test]
+fn check_embedded_without_ffprobe_still_places_subtitles() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.subtitle("Show.S01E01", "a.srt", "a");
+
+    let assert = Command::cargo_bin("subsync")
+        .unwrap()
+        .current_dir(library.root.path())
+        .env("XDG_CONFIG_HOME", library.root.path())
+        .env("PATH", library.root.path().join("empty"))
+        .arg(library.subs())
+        .arg(library.media())
+        .args([
+            "--non-interactive",
+            "--keyword",
+            "",
+            "--strategy",
+            "alphabetical",
+        ])
+        .arg("--check-embedded")
+        .assert()
+        .success();
+
+    assert!(String::from_utf8_lossy(&assert.get_output().stderr).contains("ffprobe wasn't found"));
+    assert!(library.media().join("Show.S01E01.srt").exists());
+}
+
+#[test]
+fn list_prints_candidates_and_strategy_picks() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.subtitle("Show.S01E01", "a.forced.srt", "a");
+    library.subtitle(
+        "Show.S01E01",
+        "b.en.srt",
+        "1\n00:00:01,000 --> 00:00:02,000\nHello\n",
+    );
+
+    let assert = Command::cargo_bin("subsync")
+        .unwrap()
+        .current_dir(library.root.path())
+        .env("XDG_CONFIG_HOME", library.root.path())
+        .arg("list")
+        .arg(library.subs().join("Show.S01E01"))
+        .arg(library.media().join("Show.S01E01.mkv"))
+        .assert()
+        .success();
+
+    let stdout = stdout(&assert);
+    assert!(stdout.contains(" - b.en.srt (38 B, 1 cue, en"));
+    assert!(stdout.contains(" alphabetical  a.forced.srt"));
+    assert!(stdout.contains(" size          b.en.srt"));
+    assert!(stdout.contains(" sdh           none preferred, falls back to alphabetical"));
+    assert!(!library.media().join("Show.S01E01.srt").exists());
+}
+
+#[test]
+fn list_rejects_synchronization_flags() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+
+    Command::cargo_bin("subsync")
+        .unwrap()
+        .current_dir(library.root.path())
+        .env("XDG_CONFIG_HOME", library.root.path())
+        .args(["list", "--hash"])
+        .arg(library.media().join("Show.S01E01.mkv"))
+        .assert()
+        .code(2);
+}
+
+#[test]
+fn similarity_pairs_folders_by_title() {
+    let library = Library::new();
+    library.episode("The.Matrix.1999");
+    library.episode("Heat.1995");
+    library.subtitle("The Matrix (1999)", "a.srt", "a");
+    library.subtitle("heat", "b.srt", "b");
+
+    let assert = library
+        .run(&["--strategy", "alphabetical", "--similarity", "0.9", "-v"])
+        .success();
+
+    assert_eq!(
+        read_link(library.media().join("The.Matrix.1999.srt")).unwrap(),
+        library.subs().join("The Matrix (1999)").join("a.srt")
+    );
+    assert_eq!(
+        read_link(library.media().join("Heat.1995.srt")).unwrap(),
+        library.subs().join("heat").join("b.srt")
+    );
+    assert!(stdout(&assert).contains(" - 1.000 The.Matrix.1999"));
+}
+
+#[test]
+fn similarity_flag_overrides_configured_fuzzy() {
+    let library = Library::new();
+    library.episode("The.Matrix.Reloaded.2003");
+    library.subtitle("The Matrix Reloaded", "a.srt", "a");
+    touch(&library.root.path().join("subsync.toml"), "fuzzy = 0.99\n");
+
+    let assert = library
+        .run(&["--strategy", "alphabetical", "--similarity", "0.8"])
+        .success();
+
+    assert!(stdout(&assert).contains("Similarity matched The Matrix Reloaded"));
+    assert!(library
+        .media()
+        .join("The.Matrix.Reloaded.2003.srt")
+        .is_symlink());
+}
+
+#[test]
+fn transcode_decodes_utf16_by_its_byte_order_mark() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    let text = "1\r\n00:00:01,000 --> 00:00:02,000\r\nHéllo\r\n";
+    let mut bytes = vec![0xff, 0xfe];
+    bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+    create_dir_all(library.subs().join("Show.S01E01")).unwrap();
+    write(library.subs().join("Show.S01E01/a.srt"), bytes).unwrap();
+
+    let assert = library
+        .run(&["--strategy", "alphabetical", "--copy", "--transcode"])
+        .success();
+
+    assert!(stdout(&assert).contains("from UTF-16LE"));
+    assert_eq!(
+        read_to_string(library.media().join("Show.S01E01.srt")).unwrap(),
+        text
+    );
+}
+
+#[test]
+fn archives_are_extracted_into_a_private_folder_removed_afterwards() {
+    use std::io::Write;
+
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    let mut zip =
+        zip::ZipWriter::new(std::fs::File::create(library.subs().join("Show.S01E01.zip")).unwrap());
+    zip.start_file("a.srt", zip::write::SimpleFileOptions::default())
+        .unwrap();
+    zip.write_all(b"subtitle").unwrap();
+    zip.finish().unwrap();
+    let temp = library.root.path().join("tmp");
+    create_dir_all(&temp).unwrap();
+
+    let mut command = Command::cargo_bin("subsync").unwrap();
+    command
+        .current_dir(library.root.path())
+        .env("XDG_CONFIG_HOME", library.root.path())
+        .env("TMPDIR", &temp)
+        .arg(library.subs())
+        .arg(library.media())
+        .args([
+            "--non-interactive",
+            "--keyword",
+            "",
+            "--strategy",
+            "alphabetical",
+        ])
+        .assert()
+        .success();
+
+    assert_eq!(
+        read_to_string(library.media().join("Show.S01E01.srt")).unwrap(),
+        "subtitle"
+    );
+    assert_eq!(std::fs::read_dir(&temp).unwrap().count(), 0);
+}
+
+#[test]
+fn count_mismatch_is_warned_before_synchronizing() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.episode("Show.S01E02");
+    library.episode("Show.S01E03");
+    library.subtitle("Show.S01E01", "a.srt", "a");
+    // Both streams go to one file so the order they were written in is kept
+    let log = library.root.path().join("log");
+    let file = std::fs::File::create(&log).unwrap();
+
+    let status = std::process::Command::new(assert_cmd::cargo::cargo_bin("subsync"))
+        .current_dir(library.root.path())
+        .env("XDG_CONFIG_HOME", library.root.path())
+        .arg(library.subs())
+        .arg(library.media())
+        .args([
+            "--non-interactive",
+            "--keyword",
+            "",
+            "--strategy",
+            "alphabetical",
+        ])
+        .args(["--color", "never", "-v"])
+        .stdout(file.try_clone().unwrap())
+        .stderr(file)
+        .status()
+        .unwrap();
+    assert_eq!(status.code(), Some(3));
+
+    let output = read_to_string(&log).unwrap();
+    let warning = output
+        .find("Found 1 subtitle folders for 3 media files")
+        .unwrap();
+    let missing = output.find(" - No subtitles for Show.S01E02").unwrap();
+    assert!(output.contains(" - No subtitles for Show.S01E03"));
+    let placed = output.find("symlink ").unwrap();
+    assert!(warning < missing && missing < placed);
+    assert!(library.media().join("Show.S01E01.srt").is_symlink());
+}
+
+#[test]
+fn status_lines_align_in_columns() {
+    let library = Library::new();
+    library.episode("Show.S01E01");
+    library.episode("Show.S01E10.Finale");
+    library.subtitle("Show.S01E01", "a.srt", "a");
+    library.subtitle("Show.S01E10.Finale", "b.srt", "bbbb");
+
+    let assert = library
+        .run(&["--strategy", "alphabetical", "--color", "never"])
+        .success();
+    let output = stdout(&assert);
+    assert!(output.contains("✔ Show.S01E01         symlinked  a.srt (1 B) by alphabetical\n"));
+    assert!(output.contains("✔ Show.S01E10.Finale  symlinked  b.srt (4 B) by alphabetical\n"));
+
+    std::fs::remove_file(library.media().join("Show.S01E01.srt")).unwrap();
+    std::fs::remove_file(library.media().join("Show.S01E10.Finale.srt")).unwrap();
+    let assert = library
+        .run(&["--strategy", "alphabetical", "--color", "never", "--quiet"])
+        .success();
+    assert!(!stdout(&assert).contains('✔'));
+}